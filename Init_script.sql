@@ -11,7 +11,9 @@ insert into restaurant values
     }',
     100,
     '+7xxxxxxxxxx',
-    ST_MakePoint(30.299833, 60.000142));
+    ST_MakePoint(30.299833, 60.000142),
+    'naparah',
+    'Europe/Moscow');
 
 insert into restaurant values
 	(2, 'Brasserie Kriek', 'https://yandex.ru/maps/-/CDcLNL7D', '700–1500 ₽', '₽₽', 'Европейская',
@@ -31,8 +33,11 @@ insert into restaurant values
     }',
     100,
     '+7xxxxxxxxxx',
-    ST_MakePoint(30.299903, 60.002264));
+    ST_MakePoint(30.299903, 60.002264),
+    'brasserie-kriek',
+    'Europe/Moscow');
 
+-- Sample tokens "Manager 1" and "Manager 2", stored hashed
 insert into manager values
-    (1, null, 'Manager 1', 1),
-    (2, null, 'Manager 2', 2);
\ No newline at end of file
+    (1, null, encode(sha256(convert_to('Manager 1', 'UTF8')), 'hex'), 1),
+    (2, null, encode(sha256(convert_to('Manager 2', 'UTF8')), 'hex'), 2);
\ No newline at end of file
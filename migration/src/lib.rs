@@ -1,6 +1,17 @@
 pub use sea_orm_migration::prelude::*;
 mod m20240816_222336_create_restaurant_table;
 mod m20240816_222420_create_manager_table;
+mod m20261017_101500_create_booking_state_tables;
+mod m20261017_143000_create_booking_request_tables;
+mod m20261018_091000_add_message_id_to_booking_notification;
+mod m20261018_120000_add_search_radius_to_booking_request;
+mod m20261019_093000_add_search_filters_to_booking_request;
+mod m20261020_100000_add_external_id_to_restaurant;
+mod m20261021_090000_hash_manager_tokens;
+mod m20261022_090000_notify_restaurant_changes;
+mod m20261023_090000_add_time_zone_to_restaurant;
+mod m20261024_090000_add_price_range_to_restaurant;
+mod m20261025_090000_create_dialogue_table;
 
 pub struct Migrator;
 
@@ -10,6 +21,17 @@ impl MigratorTrait for Migrator {
         vec![
             Box::new(m20240816_222336_create_restaurant_table::Migration),
             Box::new(m20240816_222420_create_manager_table::Migration),
+            Box::new(m20261017_101500_create_booking_state_tables::Migration),
+            Box::new(m20261017_143000_create_booking_request_tables::Migration),
+            Box::new(m20261018_091000_add_message_id_to_booking_notification::Migration),
+            Box::new(m20261018_120000_add_search_radius_to_booking_request::Migration),
+            Box::new(m20261019_093000_add_search_filters_to_booking_request::Migration),
+            Box::new(m20261020_100000_add_external_id_to_restaurant::Migration),
+            Box::new(m20261021_090000_hash_manager_tokens::Migration),
+            Box::new(m20261022_090000_notify_restaurant_changes::Migration),
+            Box::new(m20261023_090000_add_time_zone_to_restaurant::Migration),
+            Box::new(m20261024_090000_add_price_range_to_restaurant::Migration),
+            Box::new(m20261025_090000_create_dialogue_table::Migration),
         ]
     }
 }
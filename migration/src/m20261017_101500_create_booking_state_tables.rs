@@ -0,0 +1,91 @@
+use sea_orm_migration::{prelude::*, schema::*};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(BookingNotification::Table)
+                    .if_not_exists()
+                    .col(integer(BookingNotification::RestaurantId))
+                    .col(small_integer(BookingNotification::PersonNumber))
+                    .col(timestamp_with_time_zone(BookingNotification::ExpiresAt))
+                    .primary_key(
+                        Index::create()
+                            .col(BookingNotification::RestaurantId)
+                            .col(BookingNotification::PersonNumber),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk-booking_notification-restaurant_id")
+                            .from(BookingNotification::Table, BookingNotification::RestaurantId)
+                            .to(Restaurant::Table, Restaurant::Id)
+                            .on_delete(ForeignKeyAction::Cascade)
+                            .on_update(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_table(
+                Table::create()
+                    .table(BookingHold::Table)
+                    .if_not_exists()
+                    .col(integer(BookingHold::RestaurantId))
+                    .col(small_integer(BookingHold::PersonNumber))
+                    .col(timestamp_with_time_zone(BookingHold::ExpiresAt))
+                    .primary_key(
+                        Index::create()
+                            .col(BookingHold::RestaurantId)
+                            .col(BookingHold::PersonNumber),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk-booking_hold-restaurant_id")
+                            .from(BookingHold::Table, BookingHold::RestaurantId)
+                            .to(Restaurant::Table, Restaurant::Id)
+                            .on_delete(ForeignKeyAction::Cascade)
+                            .on_update(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(BookingHold::Table).to_owned())
+            .await?;
+
+        manager
+            .drop_table(Table::drop().table(BookingNotification::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum BookingNotification {
+    Table,
+    RestaurantId,
+    PersonNumber,
+    ExpiresAt,
+}
+
+#[derive(DeriveIden)]
+enum BookingHold {
+    Table,
+    RestaurantId,
+    PersonNumber,
+    ExpiresAt,
+}
+
+#[derive(DeriveIden)]
+enum Restaurant {
+    Table,
+    Id,
+}
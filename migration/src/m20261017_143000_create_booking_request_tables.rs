@@ -0,0 +1,117 @@
+use sea_orm_migration::{prelude::*, schema::*};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(BookingRequest::Table)
+                    .if_not_exists()
+                    .col(pk_auto(BookingRequest::Id))
+                    .col(big_integer(BookingRequest::UserChatId))
+                    .col(small_integer(BookingRequest::PersonNumber))
+                    .col(double(BookingRequest::Longitude))
+                    .col(double(BookingRequest::Latitude))
+                    .col(
+                        timestamp_with_time_zone(BookingRequest::CreatedAt)
+                            .default(Expr::current_timestamp()),
+                    )
+                    .col(string_len(BookingRequest::Status, 16).default("pending"))
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("booking_request_status_index")
+                    .table(BookingRequest::Table)
+                    .col(BookingRequest::Status)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_table(
+                Table::create()
+                    .table(BookingRequestAnswer::Table)
+                    .if_not_exists()
+                    .col(integer(BookingRequestAnswer::BookingRequestId))
+                    .col(integer(BookingRequestAnswer::RestaurantId))
+                    .col(boolean_null(BookingRequestAnswer::Approved))
+                    .col(timestamp_with_time_zone_null(
+                        BookingRequestAnswer::AnsweredAt,
+                    ))
+                    .primary_key(
+                        Index::create()
+                            .col(BookingRequestAnswer::BookingRequestId)
+                            .col(BookingRequestAnswer::RestaurantId),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk-booking_request_answer-booking_request_id")
+                            .from(
+                                BookingRequestAnswer::Table,
+                                BookingRequestAnswer::BookingRequestId,
+                            )
+                            .to(BookingRequest::Table, BookingRequest::Id)
+                            .on_delete(ForeignKeyAction::Cascade)
+                            .on_update(ForeignKeyAction::Cascade),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk-booking_request_answer-restaurant_id")
+                            .from(
+                                BookingRequestAnswer::Table,
+                                BookingRequestAnswer::RestaurantId,
+                            )
+                            .to(Restaurant::Table, Restaurant::Id)
+                            .on_delete(ForeignKeyAction::Cascade)
+                            .on_update(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(BookingRequestAnswer::Table).to_owned())
+            .await?;
+
+        manager
+            .drop_table(Table::drop().table(BookingRequest::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum BookingRequest {
+    Table,
+    Id,
+    UserChatId,
+    PersonNumber,
+    Longitude,
+    Latitude,
+    CreatedAt,
+    Status,
+}
+
+#[derive(DeriveIden)]
+enum BookingRequestAnswer {
+    Table,
+    BookingRequestId,
+    RestaurantId,
+    Approved,
+    AnsweredAt,
+}
+
+#[derive(DeriveIden)]
+enum Restaurant {
+    Table,
+    Id,
+}
@@ -0,0 +1,41 @@
+use sea_orm_migration::{prelude::*, schema::*};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(BookingRequest::Table)
+                    .add_column(string_null(BookingRequest::KitchenFilter))
+                    .add_column(string_null(BookingRequest::SegmentFilter))
+                    .add_column(string_len_null(BookingRequest::PriceBandFilter, 16))
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(BookingRequest::Table)
+                    .drop_column(BookingRequest::KitchenFilter)
+                    .drop_column(BookingRequest::SegmentFilter)
+                    .drop_column(BookingRequest::PriceBandFilter)
+                    .to_owned(),
+            )
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum BookingRequest {
+    Table,
+    KitchenFilter,
+    SegmentFilter,
+    PriceBandFilter,
+}
@@ -0,0 +1,61 @@
+use sea_orm_migration::{prelude::*, schema::*};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Restaurant::Table)
+                    .add_column(string_null(Restaurant::ExternalId))
+                    .to_owned(),
+            )
+            .await?;
+
+        // Existing restaurants are keyed by their id until they are imported
+        manager
+            .get_connection()
+            .execute_unprepared("UPDATE restaurant SET external_id = id::text")
+            .await?;
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Restaurant::Table)
+                    .modify_column(ColumnDef::new(Restaurant::ExternalId).string().not_null())
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("restaurant_external_id_index")
+                    .table(Restaurant::Table)
+                    .col(Restaurant::ExternalId)
+                    .unique()
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Restaurant::Table)
+                    .drop_column(Restaurant::ExternalId)
+                    .to_owned(),
+            )
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum Restaurant {
+    Table,
+    ExternalId,
+}
@@ -0,0 +1,82 @@
+use sea_orm_migration::{prelude::*, schema::*};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Manager::Table)
+                    .rename_column(Manager::Token, Manager::TokenHash)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Manager::Table)
+                    .add_column(timestamp_with_time_zone_null(Manager::TokenExpiresAt))
+                    .add_column(timestamp_with_time_zone_null(Manager::TokenUsedAt))
+                    .to_owned(),
+            )
+            .await?;
+
+        // Existing tokens keep working without an expiry, unless a manager
+        // has already signed in with them. They are trimmed like the entered
+        // ones before hashing
+        manager
+            .get_connection()
+            .execute_unprepared(
+                "UPDATE manager SET token_hash = \
+                 encode(sha256(convert_to(regexp_replace(token_hash, '^\\s+|\\s+$', '', 'g'), \
+                 'UTF8')), 'hex'), token_used_at = CASE WHEN tg_id IS NOT NULL THEN now() END",
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("manager_token_hash_index")
+                    .table(Manager::Table)
+                    .col(Manager::TokenHash)
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // Plaintext tokens cannot be restored, new ones have to be issued
+        manager
+            .drop_index(
+                Index::drop()
+                    .name("manager_token_hash_index")
+                    .table(Manager::Table)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Manager::Table)
+                    .drop_column(Manager::TokenExpiresAt)
+                    .drop_column(Manager::TokenUsedAt)
+                    .rename_column(Manager::TokenHash, Manager::Token)
+                    .to_owned(),
+            )
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum Manager {
+    Table,
+    Token,
+    TokenHash,
+    TokenExpiresAt,
+    TokenUsedAt,
+}
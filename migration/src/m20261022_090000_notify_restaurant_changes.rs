@@ -0,0 +1,45 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let db = manager.get_connection();
+
+        // The bot reloads its restaurant registry on these notifications.
+        // Score updates are frequent and do not concern the registry, so
+        // updates of other columns than the name are not reported.
+        db.execute_unprepared(
+            r#"CREATE FUNCTION notify_restaurant_changed() RETURNS trigger AS $$
+            BEGIN
+                PERFORM pg_notify('restaurant_changed', '');
+                RETURN NULL;
+            END;
+            $$ LANGUAGE plpgsql"#,
+        )
+        .await?;
+
+        db.execute_unprepared(
+            "CREATE TRIGGER restaurant_changed AFTER INSERT OR UPDATE OF name OR DELETE OR \
+             TRUNCATE ON restaurant FOR EACH STATEMENT EXECUTE FUNCTION \
+             notify_restaurant_changed()",
+        )
+        .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let db = manager.get_connection();
+
+        db.execute_unprepared("DROP TRIGGER restaurant_changed ON restaurant")
+            .await?;
+
+        db.execute_unprepared("DROP FUNCTION notify_restaurant_changed()")
+            .await?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,36 @@
+use sea_orm_migration::{prelude::*, schema::*};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // Existing restaurants are in Saint Petersburg
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Restaurant::Table)
+                    .add_column(string(Restaurant::TimeZone).default("Europe/Moscow"))
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Restaurant::Table)
+                    .drop_column(Restaurant::TimeZone)
+                    .to_owned(),
+            )
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum Restaurant {
+    Table,
+    TimeZone,
+}
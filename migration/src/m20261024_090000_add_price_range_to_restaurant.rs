@@ -0,0 +1,45 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // Bounds of the average price text, e.g. 700 and 1500 for "700–1500 ₽"
+        // or 1500 for "1 500 ₽". Digit groups may be separated with spaces,
+        // including no-break ones.
+        manager
+            .get_connection()
+            .execute_unprepared(
+                r#"ALTER TABLE restaurant
+                    ADD COLUMN min_price integer GENERATED ALWAYS AS ((regexp_match(
+                        regexp_replace(average_price, '[\s\u00a0\u202f]', '', 'g'),
+                        '\d{1,9}'))[1]::integer) STORED,
+                    ADD COLUMN max_price integer GENERATED ALWAYS AS ((regexp_match(
+                        regexp_replace(average_price, '[\s\u00a0\u202f]', '', 'g'),
+                        '(\d{1,9})\D*$'))[1]::integer) STORED"#,
+            )
+            .await?;
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Restaurant::Table)
+                    .drop_column(Restaurant::MinPrice)
+                    .drop_column(Restaurant::MaxPrice)
+                    .to_owned(),
+            )
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum Restaurant {
+    Table,
+    MinPrice,
+    MaxPrice,
+}
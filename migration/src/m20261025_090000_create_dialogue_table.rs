@@ -0,0 +1,33 @@
+use sea_orm_migration::{prelude::*, schema::*};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(Dialogue::Table)
+                    .if_not_exists()
+                    .col(big_integer(Dialogue::ChatId).primary_key())
+                    .col(blob(Dialogue::Dialogue))
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(Dialogue::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum Dialogue {
+    Table,
+    ChatId,
+    Dialogue,
+}
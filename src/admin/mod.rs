@@ -0,0 +1,356 @@
+//! `mest-net-admin`, the maintenance tool of the restaurant registry.
+mod restaurant_file;
+
+use crate::{
+    config::{self, AdminConfig, DatabaseConfig, LoggingConfig},
+    db::DatabaseHandler,
+    manager_tokens::{self, ManagerTokenAction, TokenActionOutcome},
+    model::restaurant_record::RestaurantRecord,
+    monitoring::{audit::TokenActor, logging},
+};
+use anyhow::{anyhow, bail, Context, Result};
+use dotenv::dotenv;
+use log::LevelFilter;
+use restaurant_file::{read_restaurants, write_restaurants, RestaurantFileFormat};
+use secrecy::ExposeSecret;
+use serde::Deserialize;
+use std::{
+    collections::HashSet,
+    env,
+    fs::File,
+    io::{BufReader, BufWriter, Write},
+    path::{Path, PathBuf},
+};
+
+const USAGE: &str = "\
+Usage:
+    mest-net-admin restaurants import <FILE> [--format <FORMAT>] [--dry-run] [--database-url <URL>]
+    mest-net-admin restaurants export <FILE> [--format <FORMAT>] [--database-url <URL>]
+    mest-net-admin tokens issue <RESTAURANT> [--ttl-hours <HOURS>] [--database-url <URL>]
+    mest-net-admin tokens rotate <RESTAURANT> [--ttl-hours <HOURS>] [--database-url <URL>]
+    mest-net-admin tokens revoke <RESTAURANT> [--database-url <URL>]
+
+FORMAT is csv, json or geojson and is taken from the file extension by default.
+Imported restaurants are matched with the existing ones by external_id.
+RESTAURANT is the external_id of a restaurant. Issue adds a manager token
+unless a manager has signed in already, rotate replaces all the tokens of the
+restaurant with a new one and revoke removes them, signing its manager out.
+
+The database, logging and token expiry settings are taken from the bot
+configuration, config.toml or MEST_NET_CONFIG, and the environment.";
+
+/// Part of the bot configuration used by the tool.
+#[derive(Deserialize)]
+struct ToolConfig {
+    database: Option<DatabaseConfig>,
+    #[serde(default)]
+    logging: LoggingConfig,
+    #[serde(default)]
+    admin: AdminConfig,
+}
+
+#[derive(Debug, PartialEq)]
+enum Command {
+    Help,
+    Import {
+        path: PathBuf,
+        format: Option<RestaurantFileFormat>,
+        dry_run: bool,
+        database_url: Option<String>,
+    },
+    Export {
+        path: PathBuf,
+        format: Option<RestaurantFileFormat>,
+        database_url: Option<String>,
+    },
+    Tokens {
+        action: ManagerTokenAction,
+        external_id: String,
+        ttl_hours: Option<u32>,
+        database_url: Option<String>,
+    },
+}
+
+/// Runs the command given in the process arguments.
+pub async fn run() -> Result<()> {
+    dotenv().ok();
+    let command = parse_args(env::args().skip(1)).map_err(|err| anyhow!("{err}\n\n{USAGE}"))?;
+    match command {
+        Command::Help => println!("{USAGE}"),
+        Command::Import {
+            path,
+            format,
+            dry_run,
+            database_url,
+        } => {
+            let config = load_config()?;
+            let format = file_format(&path, format)?;
+            let file =
+                File::open(&path).with_context(|| format!("failed to open {}", path.display()))?;
+            let records = read_restaurants(format, BufReader::new(file))
+                .with_context(|| format!("failed to read {}", path.display()))?;
+            validate_records(&records)?;
+            if dry_run {
+                println!("{} restaurants are valid", records.len());
+                return Ok(());
+            }
+            let db_handler = connect(&config, database_url).await?;
+            db_handler
+                .upsert_restaurants(&records)
+                .await
+                .context("failed to import restaurants")?;
+            println!("Imported {} restaurants", records.len());
+        }
+        Command::Export {
+            path,
+            format,
+            database_url,
+        } => {
+            let config = load_config()?;
+            let format = file_format(&path, format)?;
+            let db_handler = connect(&config, database_url).await?;
+            let records = db_handler
+                .get_all_restaurant_records()
+                .await
+                .context("failed to fetch restaurants")?;
+            let file = File::create(&path)
+                .with_context(|| format!("failed to create {}", path.display()))?;
+            let mut writer = BufWriter::new(file);
+            write_restaurants(format, &mut writer, &records)
+                .with_context(|| format!("failed to write {}", path.display()))?;
+            writer
+                .flush()
+                .with_context(|| format!("failed to write {}", path.display()))?;
+            println!("Exported {} restaurants", records.len());
+        }
+        Command::Tokens {
+            action,
+            external_id,
+            ttl_hours,
+            database_url,
+        } => {
+            let config = load_config()?;
+            let ttl_hours = ttl_hours.unwrap_or(config.admin.manager_token_ttl_hours);
+            let db_handler = connect(&config, database_url).await?;
+            let restaurant = db_handler
+                .find_restaurant_by_external_id(&external_id)
+                .await
+                .context("failed to fetch the restaurant")?
+                .with_context(|| format!("restaurant {external_id} does not exist"))?;
+            let outcome = manager_tokens::apply(
+                &db_handler,
+                restaurant.id,
+                action,
+                ttl_hours,
+                TokenActor::Cli,
+            )
+            .await
+            .context("failed to update manager tokens")?;
+            match outcome {
+                TokenActionOutcome::Issued(issued) => println!(
+                    "Manager token of {}: {}\nIt can be used once until {}",
+                    restaurant.name,
+                    issued.token,
+                    issued
+                        .expires_at
+                        .with_timezone(&restaurant.time_zone())
+                        .format("%Y-%m-%d %H:%M %:z")
+                ),
+                TokenActionOutcome::Revoked => {
+                    println!("Revoked manager tokens of {}", restaurant.name)
+                }
+                TokenActionOutcome::ManagerSignedIn => bail!(
+                    "{} already has a signed-in manager, rotate the tokens to replace them",
+                    restaurant.name
+                ),
+            }
+        }
+    }
+    Ok(())
+}
+
+fn parse_args(args: impl IntoIterator<Item = String>) -> Result<Command> {
+    let mut args = args.into_iter();
+    let mut positional = Vec::new();
+    let mut format = None;
+    let mut dry_run = false;
+    let mut ttl_hours = None;
+    let mut database_url = None;
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "-h" | "--help" => return Ok(Command::Help),
+            "--format" => {
+                let value = args.next().context("--format needs a value")?;
+                format = Some(value.parse().map_err(|err: String| anyhow!(err))?);
+            }
+            "--dry-run" => dry_run = true,
+            "--ttl-hours" => {
+                let value = args.next().context("--ttl-hours needs a value")?;
+                match value.parse() {
+                    Ok(hours) if hours > 0 => ttl_hours = Some(hours),
+                    _ => bail!("--ttl-hours must be a positive number of hours"),
+                }
+            }
+            "--database-url" => {
+                database_url = Some(args.next().context("--database-url needs a value")?);
+            }
+            _ if arg.starts_with("--") => bail!("unknown option {arg}"),
+            _ => positional.push(arg),
+        }
+    }
+    let command = match positional
+        .iter()
+        .map(String::as_str)
+        .collect::<Vec<_>>()
+        .as_slice()
+    {
+        [] => return Ok(Command::Help),
+        ["restaurants", "import", path] => Command::Import {
+            path: PathBuf::from(path),
+            format,
+            dry_run,
+            database_url,
+        },
+        ["restaurants", "export", path] => Command::Export {
+            path: PathBuf::from(path),
+            format,
+            database_url,
+        },
+        ["tokens", action, external_id] => Command::Tokens {
+            action: match *action {
+                "issue" => ManagerTokenAction::Issue,
+                "rotate" => ManagerTokenAction::Rotate,
+                "revoke" => ManagerTokenAction::Revoke,
+                _ => bail!("unknown command tokens {action}"),
+            },
+            external_id: external_id.to_string(),
+            ttl_hours,
+            database_url,
+        },
+        _ => bail!("unknown command {}", positional.join(" ")),
+    };
+    if dry_run && !matches!(command, Command::Import { .. }) {
+        bail!("--dry-run is only supported by restaurants import");
+    }
+    if format.is_some() && matches!(command, Command::Tokens { .. }) {
+        bail!("--format is only supported by restaurants import and export");
+    }
+    if ttl_hours.is_some()
+        && !matches!(
+            command,
+            Command::Tokens {
+                action: ManagerTokenAction::Issue | ManagerTokenAction::Rotate,
+                ..
+            }
+        )
+    {
+        bail!("--ttl-hours is only supported by tokens issue and rotate");
+    }
+    Ok(command)
+}
+
+fn file_format(path: &Path, format: Option<RestaurantFileFormat>) -> Result<RestaurantFileFormat> {
+    format
+        .or_else(|| RestaurantFileFormat::from_path(path))
+        .with_context(|| {
+            format!(
+                "cannot infer the format of {}, use --format",
+                path.display()
+            )
+        })
+}
+
+/// Loads the configuration and sets up logging. Not needed for the help, so
+/// that it is printed without a configuration.
+fn load_config() -> Result<ToolConfig> {
+    let config = ToolConfig::deserialize(toml::Value::Table(config::load_table()?))
+        .context("failed to load configuration")?;
+    logging::init(&config.logging, LevelFilter::Warn)?;
+    Ok(config)
+}
+
+async fn connect(config: &ToolConfig, database_url: Option<String>) -> Result<DatabaseHandler> {
+    let url = match (database_url, &config.database) {
+        (Some(url), _) => url,
+        (None, Some(database)) => database.url.expose_secret().clone(),
+        (None, None) => bail!("the database is not configured, set DATABASE_URL or --database-url"),
+    };
+    DatabaseHandler::new(url)
+        .await
+        .context("failed to connect to the database")
+}
+
+/// Checks every record and that external ids are unique, reporting all the
+/// problems at once so that a file can be fixed in one go.
+fn validate_records(records: &[RestaurantRecord]) -> Result<()> {
+    let mut external_ids = HashSet::new();
+    let mut problems = Vec::new();
+    for (index, record) in records.iter().enumerate() {
+        if let Err(err) = record.validate() {
+            problems.push(format!(
+                "record {} ({}): {err}",
+                index + 1,
+                record.external_id
+            ));
+        }
+        if !external_ids.insert(record.external_id.as_str()) {
+            problems.push(format!(
+                "record {} ({}): duplicate external_id",
+                index + 1,
+                record.external_id
+            ));
+        }
+    }
+    if !problems.is_empty() {
+        bail!("invalid restaurants:\n{}", problems.join("\n"));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        admin::{parse_args, restaurant_file::RestaurantFileFormat, Command},
+        manager_tokens::ManagerTokenAction,
+    };
+    use std::path::PathBuf;
+
+    fn parse(args: &str) -> anyhow::Result<Command> {
+        parse_args(args.split_whitespace().map(str::to_owned))
+    }
+
+    #[test]
+    fn import_options_are_parsed() {
+        assert_eq!(
+            parse("restaurants import --dry-run spb.txt --format geojson").unwrap(),
+            Command::Import {
+                path: PathBuf::from("spb.txt"),
+                format: Some(RestaurantFileFormat::GeoJson),
+                dry_run: true,
+                database_url: None,
+            }
+        );
+    }
+
+    #[test]
+    fn unknown_command_is_rejected() {
+        assert!(parse("restaurants delete spb.csv").is_err());
+        assert!(parse("restaurants export spb.csv --dry-run").is_err());
+        assert!(parse("restaurants import spb.csv --format xml").is_err());
+        assert!(parse("tokens revoke naparah --ttl-hours 24").is_err());
+    }
+
+    #[test]
+    fn token_commands_are_parsed() {
+        assert_eq!(
+            parse("tokens rotate naparah --ttl-hours 24").unwrap(),
+            Command::Tokens {
+                action: ManagerTokenAction::Rotate,
+                external_id: "naparah".to_owned(),
+                ttl_hours: Some(24),
+                database_url: None,
+            }
+        );
+        assert!(parse("tokens issue naparah --ttl-hours 0").is_err());
+    }
+}
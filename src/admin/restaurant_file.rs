@@ -0,0 +1,374 @@
+use crate::{
+    entity::restaurant::Schedule,
+    model::restaurant_record::{default_time_zone, RestaurantRecord},
+};
+use serde::{Deserialize, Serialize};
+use std::{
+    io::{Read, Write},
+    path::Path,
+    str::FromStr,
+};
+use thiserror::Error;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) enum RestaurantFileFormat {
+    /// One restaurant per row, the schedule is a JSON column.
+    Csv,
+    /// Array of restaurant records.
+    Json,
+    /// Feature collection of points with the rest of the record as properties.
+    GeoJson,
+}
+
+impl RestaurantFileFormat {
+    /// Format implied by the file extension.
+    pub(crate) fn from_path(path: &Path) -> Option<Self> {
+        path.extension()?.to_str()?.parse().ok()
+    }
+}
+
+impl FromStr for RestaurantFileFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "csv" => Ok(Self::Csv),
+            "json" => Ok(Self::Json),
+            "geojson" => Ok(Self::GeoJson),
+            _ => Err(format!("unknown format {s}, expected csv, json or geojson")),
+        }
+    }
+}
+
+#[derive(Debug, Error)]
+pub(crate) enum RestaurantFileError {
+    #[error(transparent)]
+    Csv(#[from] csv::Error),
+
+    #[error(transparent)]
+    Json(#[from] serde_json::Error),
+
+    #[error("invalid schedule of record {record}: {source}")]
+    Schedule {
+        record: usize,
+        source: serde_json::Error,
+    },
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct CsvRow {
+    external_id: String,
+    name: String,
+    maps_url: String,
+    average_price: String,
+    segment: String,
+    kitchen: String,
+    phone_number: String,
+    longitude: f64,
+    latitude: f64,
+    #[serde(default = "default_time_zone")]
+    time_zone: String,
+    schedule: String,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(tag = "type", rename = "FeatureCollection")]
+struct FeatureCollection {
+    features: Vec<Feature>,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(tag = "type", rename = "Feature")]
+struct Feature {
+    geometry: Point,
+    properties: FeatureProperties,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(tag = "type", rename = "Point")]
+struct Point {
+    /// Longitude and latitude.
+    coordinates: [f64; 2],
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct FeatureProperties {
+    external_id: String,
+    name: String,
+    maps_url: String,
+    average_price: String,
+    segment: String,
+    kitchen: String,
+    phone_number: String,
+    #[serde(default = "default_time_zone")]
+    time_zone: String,
+    schedule: Schedule,
+}
+
+/// Parses the restaurants, leaving their validation to the caller.
+pub(crate) fn read_restaurants(
+    format: RestaurantFileFormat,
+    reader: impl Read,
+) -> Result<Vec<RestaurantRecord>, RestaurantFileError> {
+    match format {
+        RestaurantFileFormat::Csv => csv::Reader::from_reader(reader)
+            .deserialize::<CsvRow>()
+            .enumerate()
+            .map(|(index, row)| {
+                let row = row?;
+                let schedule = serde_json::from_str(&row.schedule).map_err(|source| {
+                    RestaurantFileError::Schedule {
+                        record: index + 1,
+                        source,
+                    }
+                })?;
+                Ok(RestaurantRecord {
+                    external_id: row.external_id,
+                    name: row.name,
+                    maps_url: row.maps_url,
+                    average_price: row.average_price,
+                    segment: row.segment,
+                    kitchen: row.kitchen,
+                    phone_number: row.phone_number,
+                    longitude: row.longitude,
+                    latitude: row.latitude,
+                    time_zone: row.time_zone,
+                    schedule,
+                })
+            })
+            .collect(),
+        RestaurantFileFormat::Json => Ok(serde_json::from_reader(reader)?),
+        RestaurantFileFormat::GeoJson => {
+            let collection: FeatureCollection = serde_json::from_reader(reader)?;
+            Ok(collection
+                .features
+                .into_iter()
+                .map(
+                    |Feature {
+                         geometry,
+                         properties,
+                     }| RestaurantRecord {
+                        external_id: properties.external_id,
+                        name: properties.name,
+                        maps_url: properties.maps_url,
+                        average_price: properties.average_price,
+                        segment: properties.segment,
+                        kitchen: properties.kitchen,
+                        phone_number: properties.phone_number,
+                        longitude: geometry.coordinates[0],
+                        latitude: geometry.coordinates[1],
+                        time_zone: properties.time_zone,
+                        schedule: properties.schedule,
+                    },
+                )
+                .collect())
+        }
+    }
+}
+
+pub(crate) fn write_restaurants(
+    format: RestaurantFileFormat,
+    writer: impl Write,
+    records: &[RestaurantRecord],
+) -> Result<(), RestaurantFileError> {
+    match format {
+        RestaurantFileFormat::Csv => {
+            let mut writer = csv::Writer::from_writer(writer);
+            for record in records.iter().cloned() {
+                writer.serialize(CsvRow {
+                    schedule: serde_json::to_string(&record.schedule)?,
+                    external_id: record.external_id,
+                    name: record.name,
+                    maps_url: record.maps_url,
+                    average_price: record.average_price,
+                    segment: record.segment,
+                    kitchen: record.kitchen,
+                    phone_number: record.phone_number,
+                    longitude: record.longitude,
+                    latitude: record.latitude,
+                    time_zone: record.time_zone,
+                })?;
+            }
+            writer.flush().map_err(csv::Error::from)?;
+        }
+        RestaurantFileFormat::Json => serde_json::to_writer_pretty(writer, records)?,
+        RestaurantFileFormat::GeoJson => {
+            let collection = FeatureCollection {
+                features: records
+                    .iter()
+                    .cloned()
+                    .map(|record| Feature {
+                        geometry: Point {
+                            coordinates: [record.longitude, record.latitude],
+                        },
+                        properties: FeatureProperties {
+                            external_id: record.external_id,
+                            name: record.name,
+                            maps_url: record.maps_url,
+                            average_price: record.average_price,
+                            segment: record.segment,
+                            kitchen: record.kitchen,
+                            phone_number: record.phone_number,
+                            time_zone: record.time_zone,
+                            schedule: record.schedule,
+                        },
+                    })
+                    .collect(),
+            };
+            serde_json::to_writer_pretty(writer, &collection)?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        admin::restaurant_file::{
+            read_restaurants, write_restaurants, RestaurantFileError, RestaurantFileFormat,
+        },
+        entity::restaurant::{Schedule, ScheduleException, WeeklyWorkingTimes, WorkingTime},
+        model::restaurant_record::RestaurantRecord,
+    };
+    use chrono::{NaiveDate, NaiveTime};
+    use std::path::Path;
+
+    fn records() -> Vec<RestaurantRecord> {
+        let time = |hour| NaiveTime::from_hms_opt(hour, 0, 0).unwrap();
+        vec![
+            RestaurantRecord {
+                external_id: "naparah".to_owned(),
+                name: "Напарах".to_owned(),
+                maps_url: "https://yandex.ru/maps/-/CDcLmM4d".to_owned(),
+                average_price: "1000-1100 ₽".to_owned(),
+                segment: "₽₽".to_owned(),
+                kitchen: "Европейская, русская".to_owned(),
+                phone_number: "+7xxxxxxxxxx".to_owned(),
+                longitude: 30.299833,
+                latitude: 60.000142,
+                time_zone: "Europe/Moscow".to_owned(),
+                schedule: Schedule::Regular {
+                    working_time: WorkingTime {
+                        start_time: time(8),
+                        end_time: time(22),
+                    },
+                },
+            },
+            RestaurantRecord {
+                external_id: "brasserie-kriek".to_owned(),
+                name: "Brasserie Kriek, \"Петроградская\"".to_owned(),
+                maps_url: "https://yandex.ru/maps/-/CDcLqCPn".to_owned(),
+                average_price: "1500 ₽".to_owned(),
+                segment: "₽₽₽".to_owned(),
+                kitchen: "Бельгийская".to_owned(),
+                phone_number: "+7xxxxxxxxxx".to_owned(),
+                longitude: 30.309128,
+                latitude: 59.959575,
+                time_zone: "Europe/Moscow".to_owned(),
+                schedule: Schedule::WithWeekends {
+                    weekday_working_time: WorkingTime {
+                        start_time: time(12),
+                        end_time: time(0),
+                    },
+                    weekend_working_time: WorkingTime {
+                        start_time: time(12),
+                        end_time: time(2),
+                    },
+                },
+            },
+            RestaurantRecord {
+                external_id: "biblioteka".to_owned(),
+                name: "Библиотека".to_owned(),
+                maps_url: "https://yandex.ru/maps/-/CDcLuBQg".to_owned(),
+                average_price: "2000 ₽".to_owned(),
+                segment: "₽₽₽".to_owned(),
+                kitchen: "Авторская".to_owned(),
+                phone_number: "+7xxxxxxxxxx".to_owned(),
+                longitude: 30.322212,
+                latitude: 59.935367,
+                time_zone: "Asia/Vladivostok".to_owned(),
+                schedule: Schedule::Weekly {
+                    days: WeeklyWorkingTimes {
+                        monday: vec![
+                            WorkingTime {
+                                start_time: time(12),
+                                end_time: time(15),
+                            },
+                            WorkingTime {
+                                start_time: time(16),
+                                end_time: time(23),
+                            },
+                        ],
+                        friday: vec![WorkingTime {
+                            start_time: time(12),
+                            end_time: time(2),
+                        }],
+                        ..WeeklyWorkingTimes::default()
+                    },
+                    exceptions: vec![ScheduleException {
+                        date: NaiveDate::from_ymd_opt(2025, 1, 1).unwrap(),
+                        working_times: Vec::new(),
+                    }],
+                },
+            },
+        ]
+    }
+
+    #[test]
+    fn every_format_round_trips() {
+        for format in [
+            RestaurantFileFormat::Csv,
+            RestaurantFileFormat::Json,
+            RestaurantFileFormat::GeoJson,
+        ] {
+            let mut file = Vec::new();
+            write_restaurants(format, &mut file, &records()).unwrap();
+
+            assert_eq!(
+                read_restaurants(format, file.as_slice()).unwrap(),
+                records(),
+                "{format:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn format_is_inferred_from_extension() {
+        assert_eq!(
+            RestaurantFileFormat::from_path(Path::new("restaurants.GeoJSON")),
+            Some(RestaurantFileFormat::GeoJson)
+        );
+        assert_eq!(
+            RestaurantFileFormat::from_path(Path::new("restaurants.xlsx")),
+            None
+        );
+    }
+
+    #[test]
+    fn invalid_csv_schedule_is_reported_with_record_number() {
+        let csv = "external_id,name,maps_url,average_price,segment,kitchen,phone_number,\
+                   longitude,latitude,schedule\n\
+                   naparah,Напарах,https://maps.example,1000 ₽,₽₽,Русская,+7,30.3,60.0,\
+                   \"{\"\"type\"\":\"\"Always\"\"}\"\n";
+
+        let err = read_restaurants(RestaurantFileFormat::Csv, csv.as_bytes()).unwrap_err();
+
+        assert!(
+            matches!(err, RestaurantFileError::Schedule { record: 1, .. }),
+            "{err}"
+        );
+    }
+
+    #[test]
+    fn geojson_requires_points() {
+        let geojson = r#"{"type": "FeatureCollection", "features": [{
+            "type": "Feature",
+            "geometry": {"type": "LineString", "coordinates": [[30.3, 60.0], [30.4, 60.1]]},
+            "properties": {}
+        }]}"#;
+
+        assert!(read_restaurants(RestaurantFileFormat::GeoJson, geojson.as_bytes()).is_err());
+    }
+}
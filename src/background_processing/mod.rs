@@ -1 +1,3 @@
+pub(crate) mod restaurant_registry;
+pub(crate) mod shutdown;
 pub(crate) mod tasks;
@@ -0,0 +1,307 @@
+use crate::{
+    background_processing::shutdown::Shutdown,
+    config::RegistryConfig,
+    db::{DatabaseHandler, DbResult, NotificationListener},
+    model::{
+        booking_info::BookingInfo,
+        search_filters::FilterOptions,
+        types::{Db, RestaurantsNumber, SharedFilterOptions},
+    },
+    monitoring::metrics,
+    utils::constants::DEFAULT_TIME_ZONE,
+};
+use async_std::task;
+use chrono_tz::Tz;
+use scc::hash_map::Entry;
+use std::{collections::HashSet, future, sync::atomic::Ordering, time::Duration};
+use tokio::select;
+
+/// Channel notified by a trigger on the restaurant table when restaurants are
+/// added, renamed or removed.
+const RESTAURANT_CHANGES_CHANNEL: &str = "restaurant_changed";
+
+/// Brings the registry in line with the restaurant table: adds booking info
+/// for new restaurants, renames the existing ones and drops the removed ones.
+/// Restaurants with an invalid time zone are reported.
+/// The booking state of the remaining restaurants is kept, and searches
+/// running meanwhile just skip restaurants which are gone. Search filter
+/// options are collected anew.
+pub(crate) async fn sync_restaurants(
+    db_handler: &DatabaseHandler,
+    restaurants_booking_info: &Db<i32, BookingInfo>,
+    restaurants_number: &RestaurantsNumber,
+    filter_options: &SharedFilterOptions,
+) -> DbResult<()> {
+    let restaurants = db_handler.get_all_restaurants().await?;
+    *filter_options.write().unwrap() = FilterOptions::from_restaurants(&restaurants);
+    let restaurant_ids: HashSet<i32> = restaurants.iter().map(|restaurant| restaurant.id).collect();
+    let mut added = 0;
+    for restaurant in restaurants {
+        if let Err(err) = restaurant.time_zone.parse::<Tz>() {
+            log::error!(
+                "Restaurant {} with id = {} has an invalid time zone, using {}: {err}",
+                restaurant.name,
+                restaurant.id,
+                DEFAULT_TIME_ZONE
+            );
+        }
+        match restaurants_booking_info.entry_async(restaurant.id).await {
+            Entry::Occupied(mut booking_info) => {
+                if booking_info.restaurant_name != restaurant.name {
+                    booking_info.restaurant_name = restaurant.name;
+                }
+            }
+            Entry::Vacant(entry) => {
+                entry.insert_entry(BookingInfo::new(restaurant.name));
+                added += 1;
+            }
+        }
+    }
+    let mut removed = 0;
+    restaurants_booking_info
+        .retain_async(|restaurant_id, _| {
+            let exists = restaurant_ids.contains(restaurant_id);
+            if !exists {
+                removed += 1;
+            }
+            exists
+        })
+        .await;
+    restaurants_number.store(restaurant_ids.len() as u64, Ordering::Relaxed);
+    if added != 0 || removed != 0 {
+        log::info!("Restaurant registry updated: {added} added, {removed} removed");
+    }
+    Ok(())
+}
+
+/// Keeps the registry in sync until shutdown. It is reloaded on every change
+/// notification and every `refresh_interval_seconds`, in case a notification
+/// was missed while the connection was lost.
+pub(crate) async fn refresh_restaurants(
+    db_handler: DatabaseHandler,
+    restaurants_booking_info: Db<i32, BookingInfo>,
+    restaurants_number: RestaurantsNumber,
+    filter_options: SharedFilterOptions,
+    config: &RegistryConfig,
+    shutdown: Shutdown,
+) {
+    let refresh_interval = Duration::from_secs(config.refresh_interval_seconds);
+    let mut listener = None;
+    loop {
+        if config.listen_for_changes && listener.is_none() {
+            listener = match db_handler.listen(RESTAURANT_CHANGES_CHANNEL).await {
+                Ok(listener) => Some(listener),
+                Err(err) => {
+                    log::error!("Failed to listen for restaurant changes: {err}");
+                    None
+                }
+            };
+        }
+
+        let wakeup = select! {
+            _ = shutdown.triggered() => return,
+            _ = task::sleep(refresh_interval) => Ok(()),
+            notification = next_notification(&mut listener) => notification,
+        };
+        if let Err(err) = wakeup {
+            log::error!("Lost restaurant change notifications: {err}");
+            listener = None;
+        }
+
+        if let Err(err) = sync_restaurants(
+            &db_handler,
+            &restaurants_booking_info,
+            &restaurants_number,
+            &filter_options,
+        )
+        .await
+        {
+            metrics::record_database_error(err.kind());
+            log::error!("Failed to refresh restaurants: {err}");
+        }
+    }
+}
+
+async fn next_notification(listener: &mut Option<NotificationListener>) -> DbResult<()> {
+    match listener {
+        Some(listener) => listener.recv().await,
+        None => future::pending().await,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        background_processing::{
+            restaurant_registry::{refresh_restaurants, sync_restaurants},
+            shutdown::Shutdown,
+        },
+        config::RegistryConfig,
+        db::DatabaseHandler,
+        entity::restaurant::{Schedule, WorkingTime},
+        model::{
+            booking_info::BookingInfo,
+            restaurant_record::RestaurantRecord,
+            types::{Db, RestaurantsNumber, SharedFilterOptions},
+        },
+    };
+    use async_std::{future::timeout, task};
+    use chrono::{Local, NaiveTime};
+    use rand::Rng;
+    use sea_orm::{ConnectionTrait, DbBackend, Statement};
+    use std::{
+        env,
+        sync::{atomic::Ordering, Arc},
+        time::Duration,
+    };
+
+    fn record(external_id: &str, name: &str) -> RestaurantRecord {
+        RestaurantRecord {
+            external_id: external_id.to_owned(),
+            name: name.to_owned(),
+            maps_url: "https://maps.example".to_owned(),
+            average_price: "1000 ₽".to_owned(),
+            segment: "₽₽".to_owned(),
+            kitchen: "Европейская".to_owned(),
+            phone_number: "+70000000000".to_owned(),
+            longitude: 0.0,
+            latitude: 0.0,
+            time_zone: "Europe/Moscow".to_owned(),
+            schedule: Schedule::Regular {
+                working_time: WorkingTime {
+                    start_time: NaiveTime::MIN,
+                    end_time: NaiveTime::from_hms_opt(12, 0, 0).unwrap(),
+                },
+            },
+        }
+    }
+
+    async fn delete_restaurant(db_handler: &DatabaseHandler, id: i32) {
+        db_handler
+            .db
+            .execute(Statement::from_sql_and_values(
+                DbBackend::Postgres,
+                "delete from restaurant where id = $1",
+                [id.into()],
+            ))
+            .await
+            .unwrap();
+    }
+
+    /// Needs `TEST_DATABASE_URL` pointing to a database with the migrations
+    /// applied.
+    #[tokio::test]
+    #[ignore = "needs TEST_DATABASE_URL"]
+    async fn sync_keeps_booking_state_of_remaining_restaurants() {
+        let url = env::var("TEST_DATABASE_URL").expect("TEST_DATABASE_URL is not set");
+        let db_handler = DatabaseHandler::new(url).await.unwrap();
+        let suffix = rand::thread_rng().gen::<u32>();
+        let external_id = format!("registry-sync-{suffix}");
+        let id = db_handler
+            .upsert_restaurants(&[record(&external_id, "Before")])
+            .await
+            .unwrap()[0];
+        let restaurants_booking_info: Db<i32, BookingInfo> = Arc::new(scc::HashMap::new());
+        let restaurants_number = RestaurantsNumber::default();
+        let filter_options = SharedFilterOptions::default();
+        let _ = restaurants_booking_info.insert(-1, BookingInfo::new("Removed".to_owned()));
+
+        sync_restaurants(
+            &db_handler,
+            &restaurants_booking_info,
+            &restaurants_number,
+            &filter_options,
+        )
+        .await
+        .unwrap();
+        restaurants_booking_info
+            .get(&id)
+            .unwrap()
+            .set_booking_expiration_time(2, Local::now());
+        db_handler
+            .upsert_restaurants(&[record(&external_id, "After")])
+            .await
+            .unwrap();
+        sync_restaurants(
+            &db_handler,
+            &restaurants_booking_info,
+            &restaurants_number,
+            &filter_options,
+        )
+        .await
+        .unwrap();
+
+        let booking_info = restaurants_booking_info.get(&id).unwrap();
+        assert_eq!(booking_info.restaurant_name, "After");
+        assert!(booking_info.get_booking_expiration_time(2).is_some());
+        drop(booking_info);
+        assert!(!restaurants_booking_info.contains(&-1));
+        assert!(restaurants_number.load(Ordering::Relaxed) > 0);
+        assert!(filter_options
+            .read()
+            .unwrap()
+            .kitchens
+            .contains(&"Европейская".to_owned()));
+
+        delete_restaurant(&db_handler, id).await;
+        sync_restaurants(
+            &db_handler,
+            &restaurants_booking_info,
+            &restaurants_number,
+            &filter_options,
+        )
+        .await
+        .unwrap();
+        assert!(!restaurants_booking_info.contains(&id));
+    }
+
+    /// Needs `TEST_DATABASE_URL` pointing to a database with the migrations
+    /// applied.
+    #[tokio::test]
+    #[ignore = "needs TEST_DATABASE_URL"]
+    async fn restaurant_added_after_start_is_picked_up_on_notification() {
+        let url = env::var("TEST_DATABASE_URL").expect("TEST_DATABASE_URL is not set");
+        let db_handler = DatabaseHandler::new(url).await.unwrap();
+        let restaurants_booking_info: Db<i32, BookingInfo> = Arc::new(scc::HashMap::new());
+        let shutdown = Shutdown::default();
+        let refresh = {
+            let db_handler = db_handler.clone();
+            let restaurants_booking_info = restaurants_booking_info.clone();
+            let shutdown = shutdown.clone();
+            tokio::spawn(async move {
+                let config = RegistryConfig {
+                    refresh_interval_seconds: 3600,
+                    listen_for_changes: true,
+                };
+                refresh_restaurants(
+                    db_handler,
+                    restaurants_booking_info,
+                    RestaurantsNumber::default(),
+                    SharedFilterOptions::default(),
+                    &config,
+                    shutdown,
+                )
+                .await
+            })
+        };
+        // Gives the task time to subscribe
+        task::sleep(Duration::from_millis(500)).await;
+
+        let suffix = rand::thread_rng().gen::<u32>();
+        let id = db_handler
+            .upsert_restaurants(&[record(&format!("registry-notify-{suffix}"), "New")])
+            .await
+            .unwrap()[0];
+        let picked_up = timeout(Duration::from_secs(10), async {
+            while !restaurants_booking_info.contains_async(&id).await {
+                task::sleep(Duration::from_millis(20)).await;
+            }
+        })
+        .await;
+
+        shutdown.trigger();
+        refresh.await.unwrap();
+        delete_restaurant(&db_handler, id).await;
+        assert!(picked_up.is_ok());
+    }
+}
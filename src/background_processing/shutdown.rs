@@ -0,0 +1,134 @@
+use async_std::future;
+use std::{future::Future, io, time::Duration};
+use tokio::task::JoinHandle;
+use tokio_util::{sync::CancellationToken, task::TaskTracker};
+
+/// How long the tasks still running at the drain deadline get to wrap up
+/// after being cancelled.
+const CANCELLATION_GRACE_PERIOD: Duration = Duration::from_secs(5);
+
+/// Shared between the dispatcher and the background tasks to stop them
+/// together. Tasks spawned through it are awaited on shutdown.
+#[derive(Clone, Default)]
+pub(crate) struct Shutdown {
+    token: CancellationToken,
+    deadline: CancellationToken,
+    tracker: TaskTracker,
+}
+
+impl Shutdown {
+    pub(crate) fn spawn<F>(&self, task: F) -> JoinHandle<F::Output>
+    where
+        F: Future + Send + 'static,
+        F::Output: Send + 'static,
+    {
+        self.tracker.spawn(task)
+    }
+
+    pub(crate) fn trigger(&self) {
+        self.token.cancel();
+    }
+
+    pub(crate) fn is_triggered(&self) -> bool {
+        self.token.is_cancelled()
+    }
+
+    /// Completes once the shutdown has been triggered.
+    pub(crate) async fn triggered(&self) {
+        self.token.cancelled().await
+    }
+
+    pub(crate) fn is_deadline_passed(&self) -> bool {
+        self.deadline.is_cancelled()
+    }
+
+    /// Completes once [`Self::drain`] has run out of time. Tasks that can
+    /// finish their work during the drain wait for this instead of
+    /// [`Self::triggered`].
+    pub(crate) async fn deadline_passed(&self) {
+        self.deadline.cancelled().await
+    }
+
+    /// Waits for the spawned tasks to finish. If some of them are still
+    /// running after `timeout`, passes the deadline, gives them a moment to
+    /// stop and returns `false`.
+    pub(crate) async fn drain(&self, timeout: Duration) -> bool {
+        self.tracker.close();
+        if future::timeout(timeout, self.tracker.wait()).await.is_ok() {
+            return true;
+        }
+        self.deadline.cancel();
+        if future::timeout(CANCELLATION_GRACE_PERIOD, self.tracker.wait())
+            .await
+            .is_err()
+        {
+            log::warn!("Some tasks did not stop after the drain deadline");
+        }
+        false
+    }
+}
+
+/// Completes on the first Ctrl-C, or SIGTERM on Unix.
+pub(crate) async fn wait_for_termination_signal() -> io::Result<()> {
+    #[cfg(unix)]
+    {
+        use tokio::signal::unix::{signal, SignalKind};
+
+        let mut terminate = signal(SignalKind::terminate())?;
+        tokio::select! {
+            result = tokio::signal::ctrl_c() => {
+                result?;
+                log::info!("SIGINT received, shutting down");
+            }
+            _ = terminate.recv() => log::info!("SIGTERM received, shutting down"),
+        }
+    }
+    #[cfg(not(unix))]
+    {
+        tokio::signal::ctrl_c().await?;
+        log::info!("Ctrl-C received, shutting down");
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::background_processing::shutdown::Shutdown;
+    use std::{future, time::Duration};
+
+    #[tokio::test]
+    async fn drain_waits_for_tasks_stopped_by_trigger() {
+        let shutdown = Shutdown::default();
+        let task_shutdown = shutdown.clone();
+        shutdown.spawn(async move { task_shutdown.triggered().await });
+
+        shutdown.trigger();
+
+        assert!(shutdown.is_triggered());
+        assert!(shutdown.drain(Duration::from_secs(1)).await);
+        assert!(!shutdown.is_deadline_passed());
+    }
+
+    #[tokio::test]
+    async fn drain_cancels_tasks_running_at_deadline() {
+        let shutdown = Shutdown::default();
+        let task_shutdown = shutdown.clone();
+        let task = shutdown.spawn(async move { task_shutdown.deadline_passed().await });
+
+        shutdown.trigger();
+
+        assert!(!shutdown.drain(Duration::from_millis(10)).await);
+        assert!(shutdown.is_deadline_passed());
+        assert!(task.is_finished());
+    }
+
+    #[tokio::test]
+    async fn drain_gives_up_after_timeout() {
+        let shutdown = Shutdown::default();
+        shutdown.spawn(future::pending::<()>());
+
+        shutdown.trigger();
+
+        assert!(!shutdown.drain(Duration::from_millis(10)).await);
+    }
+}
@@ -1,14 +1,26 @@
 use crate::{
-    db::DatabaseHandler,
-    entity::restaurant,
+    background_processing::shutdown::Shutdown,
+    config::{AppConfig, BookingConfig, SearchConfig},
+    db::{DatabaseHandler, DbResult},
+    entity::{booking_request::BookingRequestStatus, restaurant},
     model::{
+        booking_event::BookingEvent,
         booking_info::BookingInfo,
         mest_check_command::MestCheckCommand,
+        search_filters::SearchFilters,
         types::{Db, HandlerResult},
     },
+    monitoring::{
+        audit::{self, AuditEvent, ScoreChangeReason},
+        metrics,
+    },
     utils::{
-        constants::{BOOKING_REQUEST_EXPIRATION_MINUTES, MIN_RESTAURANT_SCORE, NO_ANSWER_PENALTY},
-        keyboard::make_answer_keyboard,
+        constants::SERVICE_UNAVAILABLE_MESSAGE,
+        distance::{expand_search_radius, format_distance, format_walking_time},
+        keyboard::{
+            make_booking_request_answer_keyboard, make_cancel_search_keyboard,
+            make_restaurant_selection_keyboard, make_search_keyboard,
+        },
     },
 };
 use anyhow::Result;
@@ -16,189 +28,886 @@ use anyhow::Result;
 use async_std::task;
 use chrono::Local;
 use scc::hash_map::OccupiedEntry;
-use std::{collections::HashSet, time::Duration};
-use teloxide::{prelude::*, types::ParseMode};
+use std::{collections::HashSet, sync::Arc, time::Duration};
+use teloxide::{
+    prelude::*,
+    types::{MessageId, ParseMode},
+};
 use tokio::{
     select,
-    sync::{broadcast, mpsc::Receiver},
+    sync::{
+        broadcast::{self, error::RecvError},
+        mpsc::{self, Receiver},
+    },
     task::JoinSet,
 };
 
 type Restaurant = restaurant::RestaurantWithManagerInfo;
+type BookingRequestModel = crate::entity::booking_request::Model;
+
+pub(crate) async fn restore_booking_state(
+    db_handler: DatabaseHandler,
+    restaurants_booking_info: Db<i32, BookingInfo>,
+) -> DbResult<()> {
+    for notification in db_handler.get_all_booking_notifications().await? {
+        let person_number = notification.person_number as u8;
+        if let Some(mut booking_info) = restaurants_booking_info
+            .get_async(&notification.restaurant_id)
+            .await
+        {
+            booking_info.set_booking_request_expiration_time(
+                person_number,
+                notification.expires_at.with_timezone(&Local),
+            );
+        }
+    }
+
+    let current_time = Local::now();
+    for hold in db_handler.get_all_booking_holds().await? {
+        let person_number = hold.person_number as u8;
+        let booking_expiration_time = hold.expires_at.with_timezone(&Local);
+        if current_time > booking_expiration_time {
+            if let Err(err) = db_handler
+                .delete_booking_hold(hold.restaurant_id, person_number)
+                .await
+            {
+                log::error!("{err}");
+            }
+        } else if let Some(mut booking_info) = restaurants_booking_info
+            .get_async(&hold.restaurant_id)
+            .await
+        {
+            booking_info.set_booking_expiration_time(person_number, booking_expiration_time);
+        }
+    }
+    Ok(())
+}
 
 pub(crate) async fn send_mest_check_notification(
     bot: Bot,
     mut rx: Receiver<MestCheckCommand>,
+    event_sender: broadcast::Sender<BookingEvent>,
     db_handler: DatabaseHandler,
     restaurants_booking_info: Db<i32, BookingInfo>,
+    config: Arc<AppConfig>,
+    shutdown: Shutdown,
 ) {
-    while let Some(cmd) = rx.recv().await {
-        let person_number = cmd.person_number;
-        let restaurants: Vec<Restaurant> = db_handler
-            .find_closest_restaurants(cmd.longitude, cmd.latitude)
-            .await;
-        let mut set: JoinSet<Result<()>> = JoinSet::new();
-        for restaurant in restaurants {
-            let tg_id = restaurant.manager_tg_id;
-            let bot = bot.clone();
-            if let Some(mut booking_info) = restaurants_booking_info.get_async(&restaurant.id).await
+    // Searches still running on shutdown may start a second round, so commands
+    // are handled until the drain deadline. Commands received after it are
+    // dropped, their searches are reported as interrupted by
+    // `wait_for_restaurants_response`
+    loop {
+        let cmd = select! {
+            cmd = rx.recv() => match cmd {
+                Some(cmd) => cmd,
+                None => break,
+            },
+            _ = shutdown.deadline_passed() => break,
+        };
+        metrics::set_command_channel_depth(rx.len());
+        let booking_request_id = cmd.booking_request_id;
+        let event = match notify_restaurants(
+            &bot,
+            &db_handler,
+            &restaurants_booking_info,
+            &config,
+            booking_request_id,
+        )
+        .await
+        {
+            Ok(notified_restaurants_number) => {
+                metrics::record_restaurants_notified(notified_restaurants_number);
+                BookingEvent::RequestDispatched { booking_request_id }
+            }
+            Err(err) => {
+                log::error!(
+                    "Failed to notify restaurants about booking request with id = {}: {}",
+                    booking_request_id,
+                    err
+                );
+                BookingEvent::RequestFailed {
+                    booking_request_id,
+                    error_kind: err.kind(),
+                }
+            }
+        };
+        if let Err(err) = event_sender.send(event) {
+            log::error!("{err}");
+        }
+    }
+}
+
+/// Asks the closest restaurants, which were not asked in a previous search
+/// round, about free places. Returns the number of restaurants asked.
+async fn notify_restaurants(
+    bot: &Bot,
+    db_handler: &DatabaseHandler,
+    restaurants_booking_info: &Db<i32, BookingInfo>,
+    config: &AppConfig,
+    booking_request_id: i32,
+) -> DbResult<usize> {
+    let Some(booking_request) = db_handler
+        .find_booking_request_by_id(booking_request_id)
+        .await?
+    else {
+        return Ok(0);
+    };
+    let person_number = booking_request.person_number as u8;
+    // Restaurants asked in a previous search round are not notified again
+    let asked_restaurants_ids: HashSet<i32> = db_handler
+        .find_booking_request_answers(booking_request_id)
+        .await?
+        .into_iter()
+        .map(|answer| answer.restaurant_id)
+        .collect();
+    let restaurants: Vec<Restaurant> = db_handler
+        .find_closest_restaurants(
+            booking_request.longitude,
+            booking_request.latitude,
+            booking_request.search_radius as u16,
+            &SearchFilters::from(&booking_request),
+            &config.search,
+        )
+        .await?
+        .into_iter()
+        .filter(|restaurant| !asked_restaurants_ids.contains(&restaurant.id))
+        .collect();
+    let mut set: JoinSet<Result<()>> = JoinSet::new();
+    let mut notified_restaurants_number = 0;
+    for restaurant in restaurants {
+        let restaurant_id = restaurant.id;
+        let tg_id = restaurant.manager_tg_id;
+        let bot = bot.clone();
+        if let Some(mut booking_info) = restaurants_booking_info.get_async(&restaurant.id).await {
+            if let Some(booking_expiration_time) =
+                booking_info.get_booking_expiration_time(person_number)
             {
-                if booking_info.booking_state & (1 << person_number) != 0 {
-                    let booking_expiration_time =
-                        booking_info.get_booking_expiration_time((person_number - 1) as usize);
-                    if Local::now() > *booking_expiration_time {
-                        booking_info.booking_state &= !(1 << person_number)
-                    } else {
-                        continue;
+                if Local::now() > *booking_expiration_time {
+                    booking_info.remove_booking(person_number);
+                    if let Err(err) = db_handler
+                        .delete_booking_hold(restaurant.id, person_number)
+                        .await
+                    {
+                        log::error!("{err}");
+                    }
+                } else {
+                    if let Err(err) = db_handler
+                        .create_booking_request_answer(
+                            booking_request_id,
+                            restaurant_id,
+                            Some(true),
+                        )
+                        .await
+                    {
+                        log::error!("{err}");
                     }
+                    continue;
                 }
+            }
 
-                process_request_expirations(db_handler.clone(), &mut booking_info, restaurant)
-                    .await;
+            process_request_expirations(
+                &bot,
+                db_handler.clone(),
+                &mut booking_info,
+                &restaurant,
+                config,
+            )
+            .await;
 
-                if booking_info.notifications_state & (1 << person_number) == 0 {
-                    booking_info.notifications_state |= 1 << person_number;
-                    booking_info.set_booking_request_expiration_time(
-                        (person_number - 1) as usize,
-                        Local::now() + Duration::from_secs(BOOKING_REQUEST_EXPIRATION_MINUTES * 60),
-                    );
-                    set.spawn(async move {
-                        let person_noun_form = resolve_person_noun_form(person_number);
-                        bot.send_message(
-                            UserId(tg_id as u64),
-                            format!("У вас есть места на {person_number} {person_noun_form}?"),
-                        )
-                        .reply_markup(make_answer_keyboard())
+            if let Err(err) = db_handler
+                .create_booking_request_answer(booking_request_id, restaurant_id, None)
+                .await
+            {
+                log::error!("{err}");
+                continue;
+            }
+            notified_restaurants_number += 1;
+
+            if booking_info
+                .get_booking_request_expiration_time(person_number)
+                .is_none()
+            {
+                let booking_request_expiration_time =
+                    Local::now() + request_expiration_duration(&config.booking);
+                booking_info.set_booking_request_expiration_time(
+                    person_number,
+                    booking_request_expiration_time,
+                );
+                if let Err(err) = db_handler
+                    .save_booking_notification(
+                        restaurant_id,
+                        person_number,
+                        booking_request_expiration_time,
+                    )
+                    .await
+                {
+                    log::error!("{err}");
+                }
+                let db_handler = db_handler.clone();
+                let notification_text =
+                    format_booking_request_notification(person_number, &config.booking);
+                set.spawn(async move {
+                    let notification = bot
+                        .send_message(UserId(tg_id as u64), notification_text)
+                        .reply_markup(make_booking_request_answer_keyboard(booking_request_id))
                         .await?;
-                        Ok(())
+                    audit::record(AuditEvent::ManagerNotified {
+                        booking_request_id,
+                        restaurant_id,
+                        person_number,
                     });
-                }
+                    db_handler
+                        .set_booking_notification_message_id(
+                            restaurant_id,
+                            person_number,
+                            notification.id.0,
+                        )
+                        .await?;
+                    Ok(())
+                });
             }
         }
-        while (set.join_next().await).is_some() {}
     }
+    while (set.join_next().await).is_some() {}
+    Ok(notified_restaurants_number)
+}
+
+/// Applies the no answer penalties of all expired booking requests. Called on
+/// shutdown, since otherwise they are applied on the next request to the
+/// restaurant only.
+pub(crate) async fn flush_score_updates(
+    bot: &Bot,
+    db_handler: DatabaseHandler,
+    restaurants_booking_info: Db<i32, BookingInfo>,
+    config: &AppConfig,
+) -> DbResult<()> {
+    let mut restaurants_ids = Vec::new();
+    restaurants_booking_info
+        .scan_async(|restaurant_id, _| restaurants_ids.push(*restaurant_id))
+        .await;
+    for restaurant in db_handler.find_restaurants_by_ids(restaurants_ids).await? {
+        if let Some(mut booking_info) = restaurants_booking_info.get_async(&restaurant.id).await {
+            process_request_expirations(
+                bot,
+                db_handler.clone(),
+                &mut booking_info,
+                &restaurant,
+                config,
+            )
+            .await;
+        }
+    }
+    Ok(())
 }
 
+/// Applies the no answer penalty for every expired booking request and
+/// removes the answer buttons from its notification, so that a late answer
+/// can not approve later searches.
 async fn process_request_expirations(
+    bot: &Bot,
     db_handler: DatabaseHandler,
     booking_info: &mut OccupiedEntry<'_, i32, BookingInfo>,
-    restaurant: Restaurant,
+    restaurant: &Restaurant,
+    config: &AppConfig,
 ) {
+    let restaurant_id = restaurant.id;
+    let restaurant_score = restaurant.score;
+    let scoring_config = &config.scoring;
     let current_time = &Local::now();
     let mut total_penalty: i32 = 0;
-    for person_number in 1..booking_info.booking_request_expiration_times.len() + 1 {
-        let booking_request_expiration_time =
-            booking_info.get_booking_request_expiration_time(person_number - 1);
-        let request_expired = booking_info.notifications_state & (1 << person_number) != 0
-            && *current_time > *booking_request_expiration_time;
-        if request_expired {
-            total_penalty += NO_ANSWER_PENALTY;
-            booking_info.notifications_state &= !(1 << person_number);
+    let expired_person_numbers = booking_info.remove_expired_booking_requests(current_time);
+    metrics::record_missed_answers(expired_person_numbers.len());
+    for person_number in expired_person_numbers {
+        total_penalty += scoring_config.no_answer_penalty;
+        let notification = match db_handler
+            .find_booking_notification(restaurant_id, person_number)
+            .await
+        {
+            Ok(notification) => notification,
+            Err(err) => {
+                log::error!("{err}");
+                None
+            }
+        };
+        if let Err(err) = db_handler
+            .delete_booking_notification(restaurant_id, person_number)
+            .await
+        {
+            log::error!("{err}");
+        }
+        if let Some(message_id) = notification.and_then(|notification| notification.message_id) {
+            if let Err(err) = bot
+                .edit_message_text(
+                    UserId(restaurant.manager_tg_id as u64),
+                    MessageId(message_id),
+                    format!(
+                        "{}\n\nВремя на ответ истекло",
+                        format_booking_request_notification(person_number, &config.booking)
+                    ),
+                )
+                .await
+            {
+                log::error!("{err}");
+            }
         }
     }
     if total_penalty != 0 {
-        let score = (restaurant.score - total_penalty).max(MIN_RESTAURANT_SCORE);
-        if score != restaurant.score {
-            let _ = db_handler
-                .update_restaurant_score_wiht_raw_sql(restaurant.id, score)
-                .await;
+        let score = (restaurant_score - total_penalty).max(scoring_config.min_restaurant_score);
+        if score != restaurant_score {
+            match db_handler
+                .update_restaurant_score_wiht_raw_sql(restaurant_id, score)
+                .await
+            {
+                Ok(_) => audit::record(AuditEvent::ScoreChanged {
+                    restaurant_id,
+                    old_score: restaurant_score,
+                    new_score: score,
+                    reason: ScoreChangeReason::NoAnswer,
+                }),
+                Err(err) => log::error!("{err}"),
+            }
         }
     }
 }
 
+/// Waits for restaurants answers and keeps the search results message
+/// `results_message_id` up to date until the wait ends. If nobody approved the
+/// booking request, the search is repeated once in a wider radius. On shutdown
+/// the wait goes on until the drain deadline and then ends early with the
+/// answers received so far.
+#[allow(clippy::too_many_arguments)]
 pub(crate) async fn wait_for_restaurants_response(
     bot: Bot,
-    chat_id: ChatId,
-    mut rx: broadcast::Receiver<(i32, bool, u8)>,
+    results_message_id: MessageId,
+    command_sender: mpsc::Sender<MestCheckCommand>,
+    mut rx: broadcast::Receiver<BookingEvent>,
     db_handler: DatabaseHandler,
-    mest_check_command: MestCheckCommand,
-    restaurants_booking_info: Db<i32, BookingInfo>,
+    booking_request: BookingRequestModel,
+    config: Arc<AppConfig>,
+    shutdown: Shutdown,
 ) -> HandlerResult {
-    let person_number = mest_check_command.person_number;
-    let closest_restaurants: Vec<Restaurant> = db_handler
-        .find_closest_restaurants(mest_check_command.longitude, mest_check_command.latitude)
+    let chat_id = ChatId(booking_request.user_chat_id);
+    let booking_request_id = booking_request.id;
+    let person_number = booking_request.person_number as u8;
+    let mut search_radius = booking_request.search_radius as u16;
+    let round_end = wait_for_search_round(
+        &bot,
+        results_message_id,
+        &mut rx,
+        &db_handler,
+        &booking_request,
+        search_radius,
+        &HashSet::new(),
+        &config,
+        &shutdown,
+    )
+    .await?;
+    if let SearchRoundEnd::DispatchFailed { error_kind } = round_end {
+        return report_dispatch_failure(
+            &bot,
+            results_message_id,
+            &db_handler,
+            &booking_request,
+            error_kind,
+        )
         .await;
-    let mut awaited_restaurants_ids: HashSet<i32> = closest_restaurants
-        .iter()
-        .map(|restaurant| restaurant.id)
-        .collect();
-    let mut answered_restaurants_ids: Vec<i32> = Vec::with_capacity(closest_restaurants.len());
-    for id in &awaited_restaurants_ids {
-        if let Some(mut booking_info) = restaurants_booking_info.get_async(id).await {
-            if booking_info.booking_state & (1 << person_number) != 0 {
-                let booking_expiration_time =
-                    booking_info.get_booking_expiration_time((person_number - 1) as usize);
-                if Local::now() > *booking_expiration_time {
-                    booking_info.booking_state &= !(1 << person_number)
-                } else {
-                    answered_restaurants_ids.push(*id);
+    }
+    let answers = db_handler
+        .find_booking_request_answers(booking_request_id)
+        .await?;
+    if !shutdown.is_deadline_passed() && !answers.iter().any(|answer| answer.approved == Some(true))
+    {
+        if let Some(expanded_search_radius) = expand_search_radius(search_radius, &config.search) {
+            // Restaurants that did not answer in the first round are not
+            // waited for again
+            let asked_restaurants_ids: HashSet<i32> =
+                answers.iter().map(|answer| answer.restaurant_id).collect();
+            if db_handler
+                .expand_pending_booking_request_search_radius(
+                    booking_request_id,
+                    expanded_search_radius,
+                )
+                .await?
+            {
+                log::info!(
+                    "Nobody approved booking request with id = {} in radius of {} meters, \
+                     expanding search radius to {} meters",
+                    booking_request_id,
+                    search_radius,
+                    expanded_search_radius
+                );
+                command_sender
+                    .send(MestCheckCommand::new(booking_request_id))
+                    .await?;
+                metrics::record_command_channel_depth(&command_sender);
+                let round_end = wait_for_search_round(
+                    &bot,
+                    results_message_id,
+                    &mut rx,
+                    &db_handler,
+                    &booking_request,
+                    expanded_search_radius,
+                    &asked_restaurants_ids,
+                    &config,
+                    &shutdown,
+                )
+                .await?;
+                if let SearchRoundEnd::DispatchFailed { error_kind } = round_end {
+                    return report_dispatch_failure(
+                        &bot,
+                        results_message_id,
+                        &db_handler,
+                        &booking_request,
+                        error_kind,
+                    )
+                    .await;
                 }
+                search_radius = expanded_search_radius;
             }
         }
     }
-    for id in &answered_restaurants_ids {
-        awaited_restaurants_ids.remove(id);
+    let answered_restaurants_ids: Vec<i32> = db_handler
+        .find_booking_request_answers(booking_request_id)
+        .await?
+        .into_iter()
+        .filter(|answer| answer.approved == Some(true))
+        .map(|answer| answer.restaurant_id)
+        .collect();
+    let interrupted = answered_restaurants_ids.is_empty() && shutdown.is_deadline_passed();
+    let status = if interrupted {
+        BookingRequestStatus::Cancelled
+    } else if answered_restaurants_ids.is_empty() {
+        BookingRequestStatus::Expired
+    } else {
+        BookingRequestStatus::Answered
+    };
+    if !db_handler
+        .complete_pending_booking_request(booking_request_id, status)
+        .await?
+    {
+        log::info!(
+            "Booking request with id = {} was cancelled while waiting for answers",
+            booking_request_id
+        );
+        bot.edit_message_text(chat_id, results_message_id, "Поиск мест отменен")
+            .await?;
+        return Ok(());
     }
-    if !awaited_restaurants_ids.is_empty() {
-        select! {
-            _ = async {
-                while let Ok((id, answer, recieved_person_number)) = rx.recv().await {
-                    if recieved_person_number == person_number && awaited_restaurants_ids.contains(&id) {
-                        awaited_restaurants_ids.remove(&id);
-                        if answer {
-                            answered_restaurants_ids.push(id);
-                        }
-                        if awaited_restaurants_ids.is_empty() {
-                            break;
-                        }
-                    }
-                }
-            } => {}
-            _  = task::sleep(Duration::from_secs(BOOKING_REQUEST_EXPIRATION_MINUTES * 60)) => {}
-        }
+    if interrupted {
+        log::info!(
+            "Booking request with id = {} was interrupted by shutdown",
+            booking_request_id
+        );
+        bot.edit_message_text(
+            chat_id,
+            results_message_id,
+            "Поиск мест прерван из-за перезапуска бота, повторите его через пару минут",
+        )
+        .await?;
+        return Ok(());
     }
     let person_noun_form = resolve_person_noun_form(person_number);
     if !answered_restaurants_ids.is_empty() {
-        let answered_restaurants = db_handler
-            .find_restaurants_by_ids(answered_restaurants_ids)
-            .await;
-        let mut formatted_answer = String::new();
-        for restaurant in answered_restaurants {
-            formatted_answer.push_str(&format!("<b>•</b> {}\n", restaurant));
-            if restaurant.share_manager_contact {
-                formatted_answer.push_str(&format!(
-                    "          <a href=\"tg://user?id={}\">Предупредить о визите</a>\n",
-                    restaurant.manager_tg_id
-                ));
-            } else {
-                formatted_answer
-                    .push_str(&format!("          Телефон: {}\n", restaurant.phone_number))
-            }
-        }
-        bot.send_message(
+        let answered_restaurants = find_ranked_restaurants(
+            &db_handler,
+            answered_restaurants_ids,
+            &booking_request,
+            search_radius,
+            &config,
+        )
+        .await?;
+        let restaurants_choices = answered_restaurants
+            .iter()
+            .map(|restaurant| (restaurant.id, restaurant.name.clone()))
+            .collect::<Vec<(i32, String)>>();
+        bot.edit_message_text(
             chat_id,
+            results_message_id,
             format!(
                 "Список ресторанов, где есть места на {person_number} \
-                 {person_noun_form}:\n{formatted_answer}"
+                 {person_noun_form}:\n{}\nВыберите ресторан, и мы предупредим его о вашем визите",
+                format_restaurants_list(&answered_restaurants, &config.search)
             ),
         )
+        .reply_markup(make_restaurant_selection_keyboard(
+            booking_request_id,
+            &restaurants_choices,
+        ))
         .disable_web_page_preview(true)
         .parse_mode(ParseMode::Html)
         .await?;
     } else {
-        bot.send_message(
+        metrics::record_empty_result();
+        bot.edit_message_text(
             chat_id,
+            results_message_id,
             format!("К сожалению, мест на {person_number} {person_noun_form} нет"),
         )
         .await?;
+        bot.send_message(chat_id, "Вы можете начать новый поиск")
+            .reply_markup(make_search_keyboard())
+            .await?;
+    }
+    Ok(())
+}
+
+/// How [`wait_for_search_round`] ended.
+enum SearchRoundEnd {
+    /// The answers were received, the round timed out or the search was
+    /// cancelled or interrupted.
+    Finished,
+    DispatchFailed {
+        error_kind: &'static str,
+    },
+}
+
+/// Waits until the restaurants notified in the current search round answer,
+/// the request is cancelled, the round times out or the shutdown drain
+/// deadline passes.
+/// Restaurants in `asked_restaurants_ids` were asked in a previous round and
+/// are not waited for.
+#[allow(clippy::too_many_arguments)]
+async fn wait_for_search_round(
+    bot: &Bot,
+    results_message_id: MessageId,
+    rx: &mut broadcast::Receiver<BookingEvent>,
+    db_handler: &DatabaseHandler,
+    booking_request: &BookingRequestModel,
+    search_radius: u16,
+    asked_restaurants_ids: &HashSet<i32>,
+    config: &AppConfig,
+    shutdown: &Shutdown,
+) -> DbResult<SearchRoundEnd> {
+    let chat_id = ChatId(booking_request.user_chat_id);
+    let booking_request_id = booking_request.id;
+    select! {
+        round_end = async {
+            while let Some(event) = receive_booking_event(rx).await {
+                match event {
+                    BookingEvent::RequestDispatched { booking_request_id: id }
+                        if id == booking_request_id => break,
+                    BookingEvent::RequestFailed { booking_request_id: id, error_kind }
+                        if id == booking_request_id =>
+                    {
+                        return Ok(SearchRoundEnd::DispatchFailed { error_kind });
+                    }
+                    BookingEvent::RequestCancelled { booking_request_id: id }
+                        if id == booking_request_id => return Ok(SearchRoundEnd::Finished),
+                    _ => {}
+                }
+            }
+            let mut awaited_restaurants_ids: HashSet<i32> = db_handler
+                .find_booking_request_answers(booking_request_id)
+                .await?
+                .into_iter()
+                .filter(|answer| {
+                    answer.approved.is_none()
+                        && !asked_restaurants_ids.contains(&answer.restaurant_id)
+                })
+                .map(|answer| answer.restaurant_id)
+                .collect();
+            let refresh_progress = || {
+                update_search_progress(
+                    bot,
+                    chat_id,
+                    results_message_id,
+                    db_handler,
+                    booking_request,
+                    search_radius,
+                    config,
+                )
+            };
+            if !awaited_restaurants_ids.is_empty() {
+                refresh_progress().await?;
+            }
+            // Every answer to this request changes the answered number, answers
+            // to other requests leave the message as it is
+            while !awaited_restaurants_ids.is_empty() {
+                match receive_booking_event(rx).await {
+                    Some(BookingEvent::RequestAnswered { booking_request_id: id, restaurant_id })
+                        if id == booking_request_id =>
+                    {
+                        awaited_restaurants_ids.remove(&restaurant_id);
+                        if !awaited_restaurants_ids.is_empty() {
+                            refresh_progress().await?;
+                        }
+                    }
+                    Some(BookingEvent::RequestCancelled { booking_request_id: id })
+                        if id == booking_request_id => break,
+                    Some(_) => {}
+                    None => break,
+                }
+            }
+            Ok(SearchRoundEnd::Finished)
+        } => round_end,
+        _  = task::sleep(request_expiration_duration(&config.booking)) => {
+            Ok(SearchRoundEnd::Finished)
+        }
+        _ = shutdown.deadline_passed() => Ok(SearchRoundEnd::Finished),
+    }
+}
+
+/// Marks the booking request cancelled and tells the user that the service is
+/// unavailable, since its restaurants could not be notified.
+async fn report_dispatch_failure(
+    bot: &Bot,
+    results_message_id: MessageId,
+    db_handler: &DatabaseHandler,
+    booking_request: &BookingRequestModel,
+    error_kind: &'static str,
+) -> HandlerResult {
+    metrics::record_database_error(error_kind);
+    if db_handler
+        .complete_pending_booking_request(booking_request.id, BookingRequestStatus::Cancelled)
+        .await?
+    {
+        bot.edit_message_text(
+            ChatId(booking_request.user_chat_id),
+            results_message_id,
+            SERVICE_UNAVAILABLE_MESSAGE,
+        )
+        .await?;
+    }
+    Ok(())
+}
+
+/// Edits the search results message with restaurants that approved the
+/// booking request so far and the number of restaurants still pending.
+async fn update_search_progress(
+    bot: &Bot,
+    chat_id: ChatId,
+    results_message_id: MessageId,
+    db_handler: &DatabaseHandler,
+    booking_request: &BookingRequestModel,
+    search_radius: u16,
+    config: &AppConfig,
+) -> DbResult<()> {
+    let person_number = booking_request.person_number as u8;
+    let answers = db_handler
+        .find_booking_request_answers(booking_request.id)
+        .await?;
+    let answered_number = answers
+        .iter()
+        .filter(|answer| answer.approved.is_some())
+        .count();
+    let approved_restaurants_ids = answers
+        .iter()
+        .filter(|answer| answer.approved == Some(true))
+        .map(|answer| answer.restaurant_id)
+        .collect::<Vec<i32>>();
+    let approved_restaurants = if approved_restaurants_ids.is_empty() {
+        vec![]
+    } else {
+        find_ranked_restaurants(
+            db_handler,
+            approved_restaurants_ids,
+            booking_request,
+            search_radius,
+            config,
+        )
+        .await?
+    };
+    let person_noun_form = resolve_person_noun_form(person_number);
+    let mut text = format!(
+        "Ищем места на {person_number} {person_noun_form} в радиусе {}\n",
+        format_distance(search_radius as u32)
+    );
+    let filters = SearchFilters::from(booking_request);
+    if !filters.is_empty() {
+        text.push_str(&format!("Фильтры: {}\n", filters.describe(&config.search)));
+    }
+    text.push_str(&format!(
+        "⏳ Ответили {} из {} ресторанов\n",
+        answered_number,
+        answers.len()
+    ));
+    if !approved_restaurants.is_empty() {
+        text.push_str(&format!(
+            "\nУже есть места:\n{}",
+            format_restaurants_list(&approved_restaurants, &config.search)
+        ));
+    }
+    if let Err(err) = bot
+        .edit_message_text(chat_id, results_message_id, text)
+        .reply_markup(make_cancel_search_keyboard(booking_request.id))
+        .disable_web_page_preview(true)
+        .parse_mode(ParseMode::Html)
+        .await
+    {
+        log::error!("{err}");
     }
     Ok(())
 }
 
-fn resolve_person_noun_form<'a>(person_number: u8) -> &'a str {
-    match person_number {
-        1 => "персону",
-        2..=4 => "персоны",
+/// Fetches restaurants with their distance from the booking request location,
+/// best ranked first.
+async fn find_ranked_restaurants(
+    db_handler: &DatabaseHandler,
+    ids: Vec<i32>,
+    booking_request: &BookingRequestModel,
+    search_radius: u16,
+    config: &AppConfig,
+) -> DbResult<Vec<Restaurant>> {
+    let mut restaurants = db_handler
+        .find_restaurants_by_ids_with_distance(
+            ids,
+            booking_request.longitude,
+            booking_request.latitude,
+        )
+        .await?;
+    restaurants.sort_by(|left, right| {
+        right
+            .rank(search_radius, &config.scoring)
+            .total_cmp(&left.rank(search_radius, &config.scoring))
+    });
+    Ok(restaurants)
+}
+
+fn format_restaurants_list(restaurants: &[Restaurant], search_config: &SearchConfig) -> String {
+    let mut formatted_answer = String::new();
+    for restaurant in restaurants {
+        formatted_answer.push_str(&format!("<b>•</b> {}", restaurant));
+        if let Some(distance) = restaurant.distance {
+            formatted_answer.push_str(&format!(
+                "; {}, {}",
+                format_distance(distance.round() as u32),
+                format_walking_time(distance, search_config)
+            ));
+        }
+        formatted_answer.push('\n');
+        if restaurant.share_manager_contact {
+            formatted_answer.push_str(&format!(
+                "          <a href=\"tg://user?id={}\">Предупредить о визите</a>\n",
+                restaurant.manager_tg_id
+            ));
+        } else {
+            formatted_answer.push_str(&format!("          Телефон: {}\n", restaurant.phone_number))
+        }
+    }
+    formatted_answer
+}
+
+/// Cancels a pending booking request and withdraws notifications that no other
+/// pending request is waiting for. Restaurants are not penalized for them.
+/// Returns `false` if the request was not pending anymore.
+pub(crate) async fn cancel_booking_request(
+    bot: Bot,
+    db_handler: DatabaseHandler,
+    restaurants_booking_info: Db<i32, BookingInfo>,
+    event_sender: broadcast::Sender<BookingEvent>,
+    booking_request: BookingRequestModel,
+    booking_config: &BookingConfig,
+) -> Result<bool> {
+    let booking_request_id = booking_request.id;
+    let person_number = booking_request.person_number as u8;
+    if !db_handler
+        .complete_pending_booking_request(booking_request_id, BookingRequestStatus::Cancelled)
+        .await?
+    {
+        return Ok(false);
+    }
+
+    let awaited_restaurants_ids = db_handler
+        .find_booking_request_answers(booking_request_id)
+        .await?
+        .into_iter()
+        .filter(|answer| answer.approved.is_none())
+        .map(|answer| answer.restaurant_id)
+        .collect::<Vec<i32>>();
+    for restaurant in db_handler
+        .find_restaurants_by_ids(awaited_restaurants_ids)
+        .await?
+    {
+        if db_handler
+            .count_awaiting_booking_request_answers(restaurant.id, person_number)
+            .await?
+            > 0
+        {
+            continue;
+        }
+        let notification = db_handler
+            .find_booking_notification(restaurant.id, person_number)
+            .await?;
+        if let Some(mut booking_info) = restaurants_booking_info.get_async(&restaurant.id).await {
+            booking_info.remove_booking_request(person_number);
+        }
+        db_handler
+            .delete_booking_notification(restaurant.id, person_number)
+            .await?;
+        if let Some(message_id) = notification.and_then(|notification| notification.message_id) {
+            if let Err(err) = bot
+                .edit_message_text(
+                    UserId(restaurant.manager_tg_id as u64),
+                    MessageId(message_id),
+                    format!(
+                        "{}\n\nЗапрос отменен пользователем",
+                        format_booking_request_notification(person_number, booking_config)
+                    ),
+                )
+                .await
+            {
+                log::error!("{err}");
+            }
+        }
+    }
+
+    log::info!("Booking request with id = {} cancelled", booking_request_id);
+    if let Err(err) = event_sender.send(BookingEvent::RequestCancelled { booking_request_id }) {
+        log::error!("{err}");
+    }
+    Ok(true)
+}
+
+/// Receives the next booking event skipping over lagged ones, since the final
+/// result is always read from the database.
+async fn receive_booking_event(rx: &mut broadcast::Receiver<BookingEvent>) -> Option<BookingEvent> {
+    loop {
+        match rx.recv().await {
+            Ok(event) => return Some(event),
+            Err(RecvError::Lagged(skipped)) => {
+                log::warn!("Booking events receiver lagged by {} events", skipped)
+            }
+            Err(RecvError::Closed) => return None,
+        }
+    }
+}
+
+fn format_booking_request_notification(
+    person_number: u8,
+    booking_config: &BookingConfig,
+) -> String {
+    let person_noun_form = resolve_person_noun_form(person_number);
+    if person_number >= booking_config.large_group_min_persons {
+        format!("❗️ Большая компания ❗️\nУ вас есть места на {person_number} {person_noun_form}?")
+    } else {
+        format!("У вас есть места на {person_number} {person_noun_form}?")
+    }
+}
+
+pub(crate) fn request_expiration_duration(booking_config: &BookingConfig) -> Duration {
+    Duration::from_secs(booking_config.booking_request_expiration_minutes * 60)
+}
+
+pub(crate) fn resolve_person_noun_form<'a>(person_number: u8) -> &'a str {
+    match (person_number % 10, person_number % 100) {
+        (_, 11..=14) => "персон",
+        (1, _) => "персону",
+        (2..=4, _) => "персоны",
         _ => "персон",
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::background_processing::tasks::resolve_person_noun_form;
+
+    #[test]
+    fn person_noun_form_for_large_groups() {
+        assert_eq!(resolve_person_noun_form(1), "персону");
+        assert_eq!(resolve_person_noun_form(3), "персоны");
+        assert_eq!(resolve_person_noun_form(12), "персон");
+        assert_eq!(resolve_person_noun_form(21), "персону");
+        assert_eq!(resolve_person_noun_form(24), "персоны");
+        assert_eq!(resolve_person_noun_form(111), "персон");
+    }
+}
@@ -0,0 +1,234 @@
+use crate::{
+    background_processing::{
+        restaurant_registry::{refresh_restaurants, sync_restaurants},
+        shutdown::{wait_for_termination_signal, Shutdown},
+        tasks::{flush_score_updates, restore_booking_state, send_mest_check_notification},
+    },
+    config::{AppConfig, UpdateDeliveryMode},
+    db::DatabaseHandler,
+    model::{
+        booking_event::BookingEvent, booking_info::BookingInfo, bot_command::BotCommand,
+        mest_check_command::MestCheckCommand, types::*,
+    },
+    monitoring::{health::HealthChecks, logging},
+};
+use anyhow::{Context, Result};
+use dotenv::dotenv;
+
+use crate::schema::schema;
+
+use log::LevelFilter;
+use secrecy::ExposeSecret;
+use std::{sync::Arc, time::Duration};
+use teloxide::{
+    prelude::*, types::MenuButton, update_listeners::webhooks, utils::command::BotCommands,
+};
+use tokio::sync::{broadcast, mpsc};
+
+use crate::dialogue_storage::open_dialogue_storage;
+
+/// How long the result of the Telegram readiness check is reused.
+const TELEGRAM_CHECK_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Runs the bot until SIGINT or SIGTERM.
+pub async fn run() -> Result<()> {
+    dotenv().ok();
+    let config = Arc::new(AppConfig::load().context("failed to load configuration")?);
+    let db_handler = DatabaseHandler::new(config.database.url.expose_secret().clone())
+        .await
+        .context("failed to connect to the database")?;
+
+    logging::init(&config.logging, LevelFilter::Info)?;
+
+    log::info!("Starting Mest Net bot...");
+
+    let (command_tx, command_rx) =
+        mpsc::channel::<MestCheckCommand>(config.channels.command_channel_size);
+    let (booking_event_tx, _) =
+        broadcast::channel::<BookingEvent>(config.channels.answer_channel_size);
+
+    let restaurants_booking_info: Db<i32, BookingInfo> = Arc::new(scc::HashMap::new());
+    let restaurants_number = RestaurantsNumber::default();
+    let filter_options = SharedFilterOptions::default();
+    sync_restaurants(
+        &db_handler,
+        &restaurants_booking_info,
+        &restaurants_number,
+        &filter_options,
+    )
+    .await
+    .context("Failed to load restaurants")?;
+
+    restore_booking_state(db_handler.clone(), restaurants_booking_info.clone())
+        .await
+        .context("Failed to restore booking state")?;
+
+    let mut health_checks = HealthChecks::default();
+    {
+        let db_handler = db_handler.clone();
+        health_checks.add("postgres", move || {
+            let db_handler = db_handler.clone();
+            async move { db_handler.db.ping().await }
+        });
+    }
+
+    let dialogue_storage =
+        open_dialogue_storage(&config, db_handler.db.clone(), &mut health_checks).await?;
+
+    let bot = Bot::new(config.telegram.token.expose_secret());
+    {
+        // The Bot API is not called on every probe, so that frequent probes do
+        // not run into its rate limits
+        let bot = bot.clone();
+        health_checks.add_cached("telegram", TELEGRAM_CHECK_INTERVAL, move || {
+            let bot = bot.clone();
+            async move { bot.get_me().await.map(|_| ()) }
+        });
+    }
+
+    bot.set_my_commands(BotCommand::bot_commands()).await?;
+    bot.set_chat_menu_button()
+        .menu_button(MenuButton::Commands)
+        .await?;
+
+    let shutdown = Shutdown::default();
+
+    {
+        let bot = bot.clone();
+        let db_handler = db_handler.clone();
+        let restaurants_booking_info = restaurants_booking_info.clone();
+        let booking_event_tx = booking_event_tx.clone();
+        let config = config.clone();
+        let task_shutdown = shutdown.clone();
+        // Not tracked by the shutdown: it serves the searches being drained
+        // and stops at the drain deadline
+        let notification_task = tokio::spawn(async move {
+            send_mest_check_notification(
+                bot,
+                command_rx,
+                booking_event_tx,
+                db_handler.clone(),
+                restaurants_booking_info,
+                config,
+                task_shutdown,
+            )
+            .await
+        });
+        health_checks.add("notification_task", move || {
+            let finished = notification_task.is_finished();
+            async move {
+                if finished {
+                    Err("the task has stopped")
+                } else {
+                    Ok(())
+                }
+            }
+        });
+    }
+
+    {
+        let db_handler = db_handler.clone();
+        let restaurants_booking_info = restaurants_booking_info.clone();
+        let restaurants_number = restaurants_number.clone();
+        let filter_options = filter_options.clone();
+        let config = config.clone();
+        let task_shutdown = shutdown.clone();
+        shutdown.spawn(async move {
+            refresh_restaurants(
+                db_handler,
+                restaurants_booking_info,
+                restaurants_number,
+                filter_options,
+                &config.registry,
+                task_shutdown,
+            )
+            .await
+        });
+    }
+
+    if let Some(listen_address) = config.monitoring.listen_address {
+        let shutdown = shutdown.clone();
+        tokio::spawn(async move {
+            if let Err(err) =
+                crate::monitoring::server::serve(listen_address, health_checks, shutdown).await
+            {
+                log::error!("Monitoring server failed: {err}");
+            }
+        });
+    }
+
+    let mut dispatcher = Dispatcher::builder(bot.clone(), schema())
+        .dependencies(dptree::deps![
+            db_handler.clone(),
+            dialogue_storage.clone(),
+            restaurants_booking_info.clone(),
+            command_tx.clone(),
+            booking_event_tx.clone(),
+            config.clone(),
+            shutdown.clone(),
+            restaurants_number,
+            filter_options
+        ])
+        .build();
+
+    let drain_timeout = Duration::from_secs(config.shutdown.drain_timeout_seconds);
+    {
+        // The dispatcher keeps running during the drain, so managers can still
+        // answer the running searches, while new ones are rejected
+        let dispatcher_shutdown_token = dispatcher.shutdown_token();
+        let shutdown = shutdown.clone();
+        tokio::spawn(async move {
+            if let Err(err) = wait_for_termination_signal().await {
+                log::error!("Failed to listen for termination signals: {err}");
+                return;
+            }
+            shutdown.trigger();
+            drain_searches(&shutdown, drain_timeout).await;
+            if let Err(err) = dispatcher_shutdown_token.shutdown() {
+                log::error!("{err}");
+            }
+        });
+    }
+
+    match (&config.telegram.mode, &config.telegram.webhook) {
+        (UpdateDeliveryMode::Webhook, Some(webhook)) => {
+            let url = webhook.url().context("invalid webhook URL")?;
+            let mut options = webhooks::Options::new(webhook.listen_address, url);
+            if let Some(secret_token) = &webhook.secret_token {
+                options = options.secret_token(secret_token.expose_secret().clone());
+            }
+            let listener = webhooks::axum(bot.clone(), options)
+                .await
+                .context("failed to set up the webhook")?;
+            dispatcher
+                .dispatch_with_listener(
+                    listener,
+                    LoggingErrorHandler::with_custom_text("An error from the update listener"),
+                )
+                .await;
+        }
+        _ => dispatcher.dispatch().await,
+    }
+
+    // Already drained after a termination signal, but the dispatcher may also
+    // stop on its own
+    shutdown.trigger();
+    drain_searches(&shutdown, drain_timeout).await;
+    if let Err(err) = flush_score_updates(&bot, db_handler, restaurants_booking_info, &config).await
+    {
+        log::error!("Failed to flush score updates: {err}");
+    }
+    log::info!("Mest Net bot stopped");
+
+    Ok(())
+}
+
+async fn drain_searches(shutdown: &Shutdown, drain_timeout: Duration) {
+    log::info!("Waiting for running searches to finish...");
+    if !shutdown.drain(drain_timeout).await {
+        log::warn!(
+            "Some searches were still running after {} seconds and were cancelled",
+            drain_timeout.as_secs()
+        );
+    }
+}
@@ -0,0 +1,776 @@
+use secrecy::{ExposeSecret, Secret};
+use serde::Deserialize;
+use std::{env, fs, io, net::SocketAddr, path::PathBuf};
+use thiserror::Error;
+use toml::{Table, Value};
+use url::Url;
+
+/// Environment variable with the path of the configuration file.
+const CONFIG_PATH_ENV: &str = "MEST_NET_CONFIG";
+const DEFAULT_CONFIG_PATH: &str = "config.toml";
+/// Prefix of environment variables overriding any setting, e.g.
+/// `MEST_NET__SEARCH__MAX_RADIUS_IN_METERS=5000`.
+const ENV_OVERRIDE_PREFIX: &str = "MEST_NET__";
+const ENV_OVERRIDE_SEPARATOR: &str = "__";
+/// Environment variables used before the configuration file was introduced
+/// and whether their values are plain strings.
+const LEGACY_ENV_OVERRIDES: [(&str, &str, &str, bool); 7] = [
+    ("DATABASE_URL", "database", "url", true),
+    ("TELOXIDE_TOKEN", "telegram", "token", true),
+    ("SKYTABLE_HOST", "skytable", "host", true),
+    ("SKYTABLE_PORT", "skytable", "port", false),
+    ("SKYTABLE_USER", "skytable", "user", true),
+    ("SKYTABLE_PASSWORD", "skytable", "password", true),
+    (
+        "SKYTABLE_MAX_CONNECTIONS",
+        "skytable",
+        "max_connections",
+        false,
+    ),
+];
+
+#[derive(Debug, Error)]
+pub enum ConfigError {
+    #[error("failed to read config file {path}: {source}")]
+    Read { path: PathBuf, source: io::Error },
+
+    #[error("failed to parse config file {path}: {source}")]
+    Syntax {
+        path: PathBuf,
+        source: toml::de::Error,
+    },
+
+    #[error("invalid configuration: {0}")]
+    Parse(#[from] toml::de::Error),
+
+    #[error("invalid value of `{key}`: {reason}")]
+    Invalid { key: &'static str, reason: String },
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct AppConfig {
+    pub database: DatabaseConfig,
+    pub telegram: TelegramConfig,
+    #[serde(default)]
+    pub dialogue_storage: DialogueStorageConfig,
+    /// Required by the Skytable dialogue storage.
+    pub skytable: Option<SkytableConfig>,
+    #[serde(default)]
+    pub booking: BookingConfig,
+    #[serde(default)]
+    pub scoring: ScoringConfig,
+    #[serde(default)]
+    pub search: SearchConfig,
+    #[serde(default)]
+    pub channels: ChannelsConfig,
+    #[serde(default)]
+    pub shutdown: ShutdownConfig,
+    #[serde(default)]
+    pub monitoring: MonitoringConfig,
+    #[serde(default)]
+    pub logging: LoggingConfig,
+    #[serde(default)]
+    pub admin: AdminConfig,
+    #[serde(default)]
+    pub registry: RegistryConfig,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct DatabaseConfig {
+    pub url: Secret<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct TelegramConfig {
+    pub token: Secret<String>,
+    pub feedback_form_url: Option<String>,
+    #[serde(default)]
+    pub mode: UpdateDeliveryMode,
+    /// Required in the webhook mode.
+    pub webhook: Option<WebhookConfig>,
+}
+
+#[derive(Debug, Default, PartialEq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum UpdateDeliveryMode {
+    /// Long polling, handy for local development.
+    #[default]
+    Polling,
+    Webhook,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct WebhookConfig {
+    /// Local address the webhook server binds to, usually behind a reverse
+    /// proxy.
+    pub listen_address: SocketAddr,
+    /// Public HTTPS address of the bot, e.g. `https://bot.example.com`.
+    pub public_url: Url,
+    #[serde(default = "default_webhook_path")]
+    pub path: String,
+    /// Checked against the `X-Telegram-Bot-Api-Secret-Token` header. A random
+    /// one is generated on startup if it is not set.
+    pub secret_token: Option<Secret<String>>,
+}
+
+fn default_webhook_path() -> String {
+    "/webhook".to_owned()
+}
+
+impl WebhookConfig {
+    /// Returns the URL Telegram delivers updates to: the public URL followed
+    /// by the webhook path.
+    pub(crate) fn url(&self) -> Result<Url, url::ParseError> {
+        format!(
+            "{}{}",
+            self.public_url.as_str().trim_end_matches('/'),
+            self.path
+        )
+        .parse()
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub(crate) struct DialogueStorageConfig {
+    pub backend: DialogueStorageBackend,
+    /// Database file of the SQLite backend.
+    pub sqlite_path: PathBuf,
+}
+
+impl Default for DialogueStorageConfig {
+    fn default() -> Self {
+        DialogueStorageConfig {
+            backend: DialogueStorageBackend::default(),
+            sqlite_path: PathBuf::from("dialogues.sqlite"),
+        }
+    }
+}
+
+#[derive(Debug, Default, PartialEq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum DialogueStorageBackend {
+    /// Dialogues are lost on restart.
+    Memory,
+    Sqlite,
+    /// The `dialogue` table in the main database.
+    Postgres,
+    #[default]
+    Skytable,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct SkytableConfig {
+    pub host: String,
+    pub port: u16,
+    pub user: String,
+    pub password: Secret<String>,
+    pub max_connections: u32,
+    #[serde(default = "default_skytable_connection_timeout_ms")]
+    pub connection_timeout_ms: u64,
+    /// Attempts of every query, including the first one.
+    #[serde(default = "default_skytable_retry_attempts")]
+    pub retry_attempts: u32,
+    /// Delay before the first retry, doubled for each next one.
+    #[serde(default = "default_skytable_retry_initial_backoff_ms")]
+    pub retry_initial_backoff_ms: u64,
+}
+
+fn default_skytable_connection_timeout_ms() -> u64 {
+    5000
+}
+
+fn default_skytable_retry_attempts() -> u32 {
+    3
+}
+
+fn default_skytable_retry_initial_backoff_ms() -> u64 {
+    100
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub(crate) struct BookingConfig {
+    pub booking_expiration_minutes: u64,
+    pub booking_request_expiration_minutes: u64,
+    pub min_supported_persons: u8,
+    pub max_supported_persons: u8,
+    pub large_group_min_persons: u8,
+}
+
+impl Default for BookingConfig {
+    fn default() -> Self {
+        BookingConfig {
+            booking_expiration_minutes: 5,
+            booking_request_expiration_minutes: 2,
+            min_supported_persons: 1,
+            max_supported_persons: 100,
+            large_group_min_persons: 7,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub(crate) struct ScoringConfig {
+    pub in_time_answer_bonus: i32,
+    pub not_in_time_answer_penalty: i32,
+    pub no_answer_penalty: i32,
+    pub max_restaurant_score: i32,
+    pub min_restaurant_score: i32,
+    /// Share of proximity in the results ranking, the rest is the score.
+    pub distance_rank_weight: f64,
+}
+
+impl Default for ScoringConfig {
+    fn default() -> Self {
+        ScoringConfig {
+            in_time_answer_bonus: 3,
+            not_in_time_answer_penalty: 1,
+            no_answer_penalty: 3,
+            max_restaurant_score: 150,
+            min_restaurant_score: 0,
+            distance_rank_weight: 0.5,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub(crate) struct SearchConfig {
+    pub radius_variants_in_meters: Vec<u16>,
+    pub radius_expansion_factor: u16,
+    pub max_radius_in_meters: u16,
+    pub walking_speed_in_meters_per_minute: f64,
+    pub low_price_band_max_price: i32,
+    pub high_price_band_min_price: i32,
+}
+
+impl Default for SearchConfig {
+    fn default() -> Self {
+        SearchConfig {
+            radius_variants_in_meters: vec![500, 1000, 3000],
+            radius_expansion_factor: 3,
+            max_radius_in_meters: 5000,
+            walking_speed_in_meters_per_minute: 80.0,
+            low_price_band_max_price: 1000,
+            high_price_band_min_price: 2000,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub(crate) struct ChannelsConfig {
+    pub command_channel_size: usize,
+    pub answer_channel_size: usize,
+}
+
+impl Default for ChannelsConfig {
+    fn default() -> Self {
+        ChannelsConfig {
+            command_channel_size: 32,
+            answer_channel_size: 128,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub(crate) struct ShutdownConfig {
+    /// How long running searches may take to finish after SIGINT or SIGTERM.
+    pub drain_timeout_seconds: u64,
+}
+
+impl Default for ShutdownConfig {
+    fn default() -> Self {
+        ShutdownConfig {
+            drain_timeout_seconds: 30,
+        }
+    }
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub(crate) struct MonitoringConfig {
+    /// Address of the HTTP server with the `/metrics`, `/health/live` and
+    /// `/health/ready` endpoints, disabled if not set.
+    pub listen_address: Option<SocketAddr>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub(crate) struct LoggingConfig {
+    /// log4rs configuration file. Everything, including the `audit` target,
+    /// goes to the console at the info level if not set.
+    pub config_file: Option<PathBuf>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub(crate) struct AdminConfig {
+    /// Telegram ids of the users allowed to issue and revoke manager tokens
+    /// through the bot.
+    pub super_admin_ids: Vec<i64>,
+    pub manager_token_ttl_hours: u32,
+}
+
+impl Default for AdminConfig {
+    fn default() -> Self {
+        AdminConfig {
+            super_admin_ids: Vec::new(),
+            manager_token_ttl_hours: 72,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub(crate) struct RegistryConfig {
+    /// How often the restaurants are reloaded from the database regardless of
+    /// change notifications.
+    pub refresh_interval_seconds: u64,
+    /// Reloads the restaurants as soon as the database reports a change.
+    pub listen_for_changes: bool,
+}
+
+impl Default for RegistryConfig {
+    fn default() -> Self {
+        RegistryConfig {
+            refresh_interval_seconds: 300,
+            listen_for_changes: true,
+        }
+    }
+}
+
+/// Reads the configuration file from `MEST_NET_CONFIG` or `config.toml` and
+/// applies environment overrides. The default file may be absent if
+/// everything is set through the environment.
+pub(crate) fn load_table() -> Result<Table, ConfigError> {
+    let (path, required) = match env::var(CONFIG_PATH_ENV) {
+        Ok(path) => (PathBuf::from(path), true),
+        Err(_) => (PathBuf::from(DEFAULT_CONFIG_PATH), false),
+    };
+    let mut table = match fs::read_to_string(&path) {
+        Ok(content) => content
+            .parse::<Table>()
+            .map_err(|source| ConfigError::Syntax {
+                path: path.clone(),
+                source,
+            })?,
+        Err(err) if err.kind() == io::ErrorKind::NotFound && !required => Table::new(),
+        Err(source) => return Err(ConfigError::Read { path, source }),
+    };
+    apply_env_overrides(&mut table, env::vars());
+    Ok(table)
+}
+
+impl AppConfig {
+    /// Loads the configuration with [`load_table`] and validates it.
+    pub(crate) fn load() -> Result<Self, ConfigError> {
+        Self::from_table(load_table()?)
+    }
+
+    pub(crate) fn from_table(table: Table) -> Result<Self, ConfigError> {
+        let config = AppConfig::deserialize(Value::Table(table))?;
+        config.validate()?;
+        Ok(config)
+    }
+
+    fn validate(&self) -> Result<(), ConfigError> {
+        fn check(key: &'static str, valid: bool, reason: &str) -> Result<(), ConfigError> {
+            if valid {
+                Ok(())
+            } else {
+                Err(ConfigError::Invalid {
+                    key,
+                    reason: reason.to_owned(),
+                })
+            }
+        }
+
+        let booking = &self.booking;
+        check(
+            "booking.booking_expiration_minutes",
+            booking.booking_expiration_minutes > 0,
+            "must be positive",
+        )?;
+        check(
+            "booking.booking_request_expiration_minutes",
+            booking.booking_request_expiration_minutes > 0,
+            "must be positive",
+        )?;
+        check(
+            "booking.min_supported_persons",
+            booking.min_supported_persons > 0,
+            "must be positive",
+        )?;
+        check(
+            "booking.large_group_min_persons",
+            (booking.min_supported_persons..=booking.max_supported_persons)
+                .contains(&booking.large_group_min_persons),
+            "must be between min_supported_persons and max_supported_persons",
+        )?;
+
+        let scoring = &self.scoring;
+        check(
+            "scoring.max_restaurant_score",
+            scoring.max_restaurant_score > scoring.min_restaurant_score,
+            "must be greater than min_restaurant_score",
+        )?;
+        check(
+            "scoring.distance_rank_weight",
+            (0.0..=1.0).contains(&scoring.distance_rank_weight),
+            "must be between 0 and 1",
+        )?;
+
+        let search = &self.search;
+        check(
+            "search.radius_variants_in_meters",
+            !search.radius_variants_in_meters.is_empty()
+                && search
+                    .radius_variants_in_meters
+                    .iter()
+                    .all(|radius| (1..=search.max_radius_in_meters).contains(radius)),
+            "must be a non-empty list of radiuses not greater than max_radius_in_meters",
+        )?;
+        check(
+            "search.radius_expansion_factor",
+            search.radius_expansion_factor > 0,
+            "must be positive",
+        )?;
+        check(
+            "search.walking_speed_in_meters_per_minute",
+            search.walking_speed_in_meters_per_minute > 0.0,
+            "must be positive",
+        )?;
+        check(
+            "search.high_price_band_min_price",
+            search.high_price_band_min_price > search.low_price_band_max_price,
+            "must be greater than low_price_band_max_price",
+        )?;
+
+        check(
+            "channels.command_channel_size",
+            self.channels.command_channel_size > 0,
+            "must be positive",
+        )?;
+        check(
+            "channels.answer_channel_size",
+            self.channels.answer_channel_size > 0,
+            "must be positive",
+        )?;
+        if self.dialogue_storage.backend == DialogueStorageBackend::Skytable {
+            check(
+                "skytable",
+                self.skytable.is_some(),
+                "must be set for the skytable dialogue storage",
+            )?;
+        }
+        if let Some(skytable) = &self.skytable {
+            check(
+                "skytable.max_connections",
+                skytable.max_connections > 0,
+                "must be positive",
+            )?;
+            check(
+                "skytable.connection_timeout_ms",
+                skytable.connection_timeout_ms > 0,
+                "must be positive",
+            )?;
+            check(
+                "skytable.retry_attempts",
+                skytable.retry_attempts > 0,
+                "must be positive",
+            )?;
+        }
+
+        check(
+            "registry.refresh_interval_seconds",
+            self.registry.refresh_interval_seconds > 0,
+            "must be positive",
+        )?;
+        check(
+            "admin.manager_token_ttl_hours",
+            self.admin.manager_token_ttl_hours > 0,
+            "must be positive",
+        )?;
+
+        let telegram = &self.telegram;
+        if telegram.mode == UpdateDeliveryMode::Webhook {
+            check(
+                "telegram.webhook",
+                telegram.webhook.is_some(),
+                "must be set in the webhook mode",
+            )?;
+        }
+        if let Some(webhook) = &telegram.webhook {
+            check(
+                "telegram.webhook.public_url",
+                webhook.public_url.scheme() == "https",
+                "must be an https URL",
+            )?;
+            check(
+                "telegram.webhook.path",
+                webhook.path.starts_with('/') && webhook.url().is_ok(),
+                "must be a URL path starting with /",
+            )?;
+            if let Some(secret_token) = &webhook.secret_token {
+                let secret_token = secret_token.expose_secret();
+                check(
+                    "telegram.webhook.secret_token",
+                    (1..=256).contains(&secret_token.len())
+                        && secret_token
+                            .chars()
+                            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-'),
+                    "must be 1-256 characters of A-Z, a-z, 0-9, _ and -",
+                )?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Sets `section.key` values from the legacy and the `MEST_NET__` prefixed
+/// environment variables. Prefixed values are parsed as TOML, so numbers and
+/// lists keep their types, and fall back to plain strings. A numeric string
+/// has to be quoted, e.g. `MEST_NET__SKYTABLE__PASSWORD='"123"'`.
+fn apply_env_overrides(table: &mut Table, vars: impl IntoIterator<Item = (String, String)>) {
+    for (name, value) in vars {
+        let (path, value) = match LEGACY_ENV_OVERRIDES
+            .iter()
+            .find(|(legacy_name, _, _, _)| *legacy_name == name)
+        {
+            Some((_, section, key, is_string)) => (
+                vec![section.to_string(), key.to_string()],
+                if *is_string {
+                    Value::String(value)
+                } else {
+                    parse_env_value(&value)
+                },
+            ),
+            None => match name.strip_prefix(ENV_OVERRIDE_PREFIX) {
+                Some(path) => (
+                    path.split(ENV_OVERRIDE_SEPARATOR)
+                        .map(str::to_lowercase)
+                        .collect::<Vec<String>>(),
+                    parse_env_value(&value),
+                ),
+                None => continue,
+            },
+        };
+        let Some((key, sections)) = path.split_last() else {
+            continue;
+        };
+        let mut current = &mut *table;
+        for section in sections {
+            let entry = current
+                .entry(section.clone())
+                .or_insert_with(|| Value::Table(Table::new()));
+            if !entry.is_table() {
+                *entry = Value::Table(Table::new());
+            }
+            current = entry.as_table_mut().unwrap();
+        }
+        current.insert(key.clone(), value);
+    }
+}
+
+fn parse_env_value(value: &str) -> Value {
+    format!("value = {value}")
+        .parse::<Table>()
+        .ok()
+        .and_then(|mut table| table.remove("value"))
+        .unwrap_or_else(|| Value::String(value.to_owned()))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::config::{
+        apply_env_overrides, AppConfig, ConfigError, DialogueStorageBackend, UpdateDeliveryMode,
+    };
+    use secrecy::ExposeSecret;
+    use toml::Table;
+
+    const MINIMAL_CONFIG: &str = r#"
+        [database]
+        url = "postgres://localhost/mest_net"
+
+        [telegram]
+        token = "token"
+
+        [skytable]
+        host = "localhost"
+        port = 2003
+        user = "root"
+        password = "password"
+        max_connections = 4
+    "#;
+
+    fn vars(vars: &[(&str, &str)]) -> Vec<(String, String)> {
+        vars.iter()
+            .map(|(name, value)| (name.to_string(), value.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn minimal_config_uses_defaults() {
+        let config = AppConfig::from_table(MINIMAL_CONFIG.parse::<Table>().unwrap()).unwrap();
+
+        assert_eq!(config.booking.booking_request_expiration_minutes, 2);
+        assert_eq!(
+            config.search.radius_variants_in_meters,
+            vec![500, 1000, 3000]
+        );
+        assert_eq!(config.telegram.feedback_form_url, None);
+    }
+
+    #[test]
+    fn example_config_is_valid() {
+        let table = include_str!("../config.example.toml")
+            .parse::<Table>()
+            .unwrap();
+
+        assert!(AppConfig::from_table(table).is_ok());
+    }
+
+    #[test]
+    fn env_overrides_take_precedence() {
+        let mut table = MINIMAL_CONFIG.parse::<Table>().unwrap();
+        apply_env_overrides(
+            &mut table,
+            vars(&[
+                ("SKYTABLE_PORT", "2004"),
+                ("SKYTABLE_PASSWORD", "123"),
+                ("DATABASE_URL", "postgres://db/mest_net"),
+                ("MEST_NET__SEARCH__RADIUS_VARIANTS_IN_METERS", "[300, 600]"),
+                (
+                    "MEST_NET__TELEGRAM__FEEDBACK_FORM_URL",
+                    "https://forms.example",
+                ),
+                ("UNRELATED", "1"),
+            ]),
+        );
+        let config = AppConfig::from_table(table).unwrap();
+
+        let skytable = config.skytable.unwrap();
+        assert_eq!(skytable.port, 2004);
+        assert_eq!(skytable.password.expose_secret(), "123");
+        assert_eq!(
+            config.database.url.expose_secret(),
+            "postgres://db/mest_net"
+        );
+        assert_eq!(config.search.radius_variants_in_meters, vec![300, 600]);
+        assert_eq!(
+            config.telegram.feedback_form_url.as_deref(),
+            Some("https://forms.example")
+        );
+    }
+
+    #[test]
+    fn missing_setting_is_reported() {
+        let mut table = MINIMAL_CONFIG.parse::<Table>().unwrap();
+        table.remove("database");
+
+        let err = AppConfig::from_table(table).unwrap_err();
+
+        assert!(matches!(err, ConfigError::Parse(_)));
+        assert!(err.to_string().contains("database"));
+    }
+
+    #[test]
+    fn skytable_is_optional_for_other_dialogue_storages() {
+        let mut table = MINIMAL_CONFIG.parse::<Table>().unwrap();
+        table.remove("skytable");
+
+        let err = AppConfig::from_table(table.clone()).unwrap_err();
+        assert!(matches!(
+            err,
+            ConfigError::Invalid {
+                key: "skytable",
+                ..
+            }
+        ));
+
+        apply_env_overrides(
+            &mut table,
+            vars(&[("MEST_NET__DIALOGUE_STORAGE__BACKEND", "sqlite")]),
+        );
+        let config = AppConfig::from_table(table).unwrap();
+        assert_eq!(
+            config.dialogue_storage.backend,
+            DialogueStorageBackend::Sqlite
+        );
+    }
+
+    #[test]
+    fn webhook_mode_is_configured() {
+        let mut table = MINIMAL_CONFIG.parse::<Table>().unwrap();
+        apply_env_overrides(
+            &mut table,
+            vars(&[
+                ("MEST_NET__TELEGRAM__MODE", "webhook"),
+                (
+                    "MEST_NET__TELEGRAM__WEBHOOK__LISTEN_ADDRESS",
+                    "127.0.0.1:8080",
+                ),
+                (
+                    "MEST_NET__TELEGRAM__WEBHOOK__PUBLIC_URL",
+                    "https://bot.example.com/",
+                ),
+                (
+                    "MEST_NET__TELEGRAM__WEBHOOK__SECRET_TOKEN",
+                    "secret_token-1",
+                ),
+            ]),
+        );
+        let config = AppConfig::from_table(table).unwrap();
+        let webhook = config.telegram.webhook.unwrap();
+
+        assert_eq!(config.telegram.mode, UpdateDeliveryMode::Webhook);
+        assert_eq!(
+            webhook.url().unwrap().as_str(),
+            "https://bot.example.com/webhook"
+        );
+    }
+
+    #[test]
+    fn webhook_mode_requires_webhook_settings() {
+        let mut table = MINIMAL_CONFIG.parse::<Table>().unwrap();
+        apply_env_overrides(&mut table, vars(&[("MEST_NET__TELEGRAM__MODE", "webhook")]));
+
+        let err = AppConfig::from_table(table).unwrap_err();
+
+        assert!(matches!(
+            err,
+            ConfigError::Invalid {
+                key: "telegram.webhook",
+                ..
+            }
+        ));
+    }
+
+    #[test]
+    fn invalid_setting_is_reported() {
+        let mut table = MINIMAL_CONFIG.parse::<Table>().unwrap();
+        apply_env_overrides(
+            &mut table,
+            vars(&[("MEST_NET__SCORING__DISTANCE_RANK_WEIGHT", "2.0")]),
+        );
+
+        let err = AppConfig::from_table(table).unwrap_err();
+
+        assert!(matches!(
+            err,
+            ConfigError::Invalid {
+                key: "scoring.distance_rank_weight",
+                ..
+            }
+        ));
+    }
+}
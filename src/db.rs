@@ -1,19 +1,88 @@
 use crate::{
+    config::SearchConfig,
     entity::{
+        booking_hold, booking_notification,
+        booking_request::{self, BookingRequestStatus},
+        booking_request_answer,
         manager::{self},
-        prelude::{Manager, Restaurant},
+        prelude::{
+            BookingHold, BookingNotification, BookingRequest, BookingRequestAnswer, Manager,
+            Restaurant,
+        },
         restaurant::{self, RestaurantWithManagerInfo},
     },
-    utils::constants::SEARCH_RADIUS_IN_METERS,
+    model::{restaurant_record::RestaurantRecord, search_filters::SearchFilters},
 };
+use chrono::{DateTime, Local};
 use sea_orm::{
     prelude::Expr,
-    sea_query::{Alias, IntoCondition},
-    ActiveModelTrait, ColumnTrait, ConnectOptions, ConnectionTrait, Database, DatabaseConnection,
-    DbBackend, DbErr, EntityTrait, ExecResult, IntoSimpleExpr, JoinType, PaginatorTrait,
-    QueryFilter, QueryOrder, QuerySelect, RelationTrait, Statement,
+    sea_query::{Alias, IntoCondition, OnConflict},
+    sqlx::{postgres::PgListener, Error as SqlxError},
+    ActiveModelTrait,
+    ActiveValue::Set,
+    ColumnTrait, ConnectOptions, ConnectionTrait, Database, DatabaseConnection, DbBackend, DbErr,
+    EntityTrait, FromQueryResult, IntoSimpleExpr, JoinType, QueryFilter, QueryOrder, QuerySelect,
+    RelationTrait, RuntimeErr, Select, Statement, TransactionTrait,
 };
-use std::env;
+use thiserror::Error;
+
+/// Failure of a [`DatabaseHandler`] call. An outage is told apart from other
+/// errors, so that users can be asked to retry later.
+#[derive(Debug, Error)]
+pub enum DatabaseError {
+    #[error("database is unavailable: {0}")]
+    Unavailable(#[source] DbErr),
+
+    #[error("database error: {0}")]
+    Query(#[source] DbErr),
+}
+
+impl From<DbErr> for DatabaseError {
+    fn from(err: DbErr) -> Self {
+        let connection_lost = match &err {
+            DbErr::Conn(_) | DbErr::ConnectionAcquire(_) => true,
+            DbErr::Exec(RuntimeErr::SqlxError(err)) | DbErr::Query(RuntimeErr::SqlxError(err)) => {
+                matches!(
+                    err,
+                    SqlxError::Io(_) | SqlxError::PoolTimedOut | SqlxError::PoolClosed
+                )
+            }
+            _ => false,
+        };
+        if connection_lost {
+            DatabaseError::Unavailable(err)
+        } else {
+            DatabaseError::Query(err)
+        }
+    }
+}
+
+impl DatabaseError {
+    pub fn kind(&self) -> &'static str {
+        match self {
+            DatabaseError::Unavailable(_) => "unavailable",
+            DatabaseError::Query(_) => "query",
+        }
+    }
+}
+
+pub type DbResult<T> = Result<T, DatabaseError>;
+
+fn runtime_error(err: SqlxError) -> DatabaseError {
+    DbErr::Exec(RuntimeErr::SqlxError(err)).into()
+}
+
+/// Postgres notifications of the channel given to [`DatabaseHandler::listen`].
+pub struct NotificationListener(PgListener);
+
+impl NotificationListener {
+    /// Waits for the next notification. Notifications sent while the
+    /// connection is lost are missed, it is restored on the next call.
+    pub async fn recv(&mut self) -> DbResult<()> {
+        self.0.recv().await.map_err(runtime_error)?;
+        Ok(())
+    }
+}
 
 #[derive(Clone)]
 pub struct DatabaseHandler {
@@ -23,77 +92,147 @@ pub struct DatabaseHandler {
 type RestaurantModel = crate::entity::restaurant::Model;
 type ManagerModel = crate::entity::manager::Model;
 type ManagerActiveModel = crate::entity::manager::ActiveModel;
+type BookingNotificationModel = crate::entity::booking_notification::Model;
+type BookingHoldModel = crate::entity::booking_hold::Model;
+type BookingRequestModel = crate::entity::booking_request::Model;
+type BookingRequestAnswerModel = crate::entity::booking_request_answer::Model;
+
+async fn insert_manager(
+    db: &impl ConnectionTrait,
+    restaurant_id: i32,
+    token_hash: String,
+    expires_at: DateTime<Local>,
+) -> Result<i32, DbErr> {
+    let manager = ManagerActiveModel {
+        token_hash: Set(token_hash),
+        restaurant_id: Set(restaurant_id),
+        token_expires_at: Set(Some(expires_at.fixed_offset())),
+        ..Default::default()
+    }
+    .insert(db)
+    .await?;
+    Ok(manager.id)
+}
+
+async fn delete_managers(db: &impl ConnectionTrait, restaurant_id: i32) -> Result<Vec<i32>, DbErr> {
+    db.query_all(Statement::from_sql_and_values(
+        DbBackend::Postgres,
+        "delete from manager where restaurant_id = $1 returning id",
+        [restaurant_id.into()],
+    ))
+    .await?
+    .iter()
+    .map(|row| row.try_get("", "id"))
+    .collect()
+}
 
 impl DatabaseHandler {
-    pub async fn new(uri: String) -> Self {
+    pub async fn new(uri: String) -> DbResult<Self> {
         let mut opt = ConnectOptions::new(uri);
         opt.sqlx_logging(false);
 
-        let db = Database::connect(opt).await.unwrap();
-
-        DatabaseHandler { db }
-    }
+        let db = Database::connect(opt).await?;
 
-    pub async fn from_env() -> Self {
-        Self::new(env::var("DATABASE_URL").unwrap()).await
+        Ok(DatabaseHandler { db })
     }
 
-    pub async fn get_all_restaurants(&self) -> Vec<RestaurantModel> {
+    pub async fn get_all_restaurants(&self) -> DbResult<Vec<RestaurantModel>> {
         log::info!("Fetching all restaurants");
-        Restaurant::find()
-            .all(&self.db)
-            .await
-            .unwrap_or_else(|err| {
-                log::error!("Error while fetching all restaurants: {:?}", err);
-                vec![]
-            })
+        Ok(Restaurant::find().all(&self.db).await?)
     }
 
-    pub async fn find_restaurant_by_id(&self, id: i32) -> Option<RestaurantModel> {
+    pub async fn find_restaurant_by_id(&self, id: i32) -> DbResult<Option<RestaurantModel>> {
         log::info!("Fetching restaurant by id = {}", id);
-        Restaurant::find_by_id(id)
-            .one(&self.db)
-            .await
-            .unwrap_or_else(|err| {
-                log::error!("Error while fetching restaurant by id = {}: {:?}", id, err);
-                None
-            })
+        Ok(Restaurant::find_by_id(id).one(&self.db).await?)
     }
 
     pub async fn find_closest_restaurants(
         &self,
         longitude: f64,
         latitude: f64,
-    ) -> Vec<RestaurantWithManagerInfo> {
+        search_radius: u16,
+        filters: &SearchFilters,
+        search_config: &SearchConfig,
+    ) -> DbResult<Vec<RestaurantWithManagerInfo>> {
         log::info!(
-            "Fetching closest restaurant with longtitude = {}, latitude = {} in radius of {} \
-             meters",
-            longitude,
-            latitude,
-            SEARCH_RADIUS_IN_METERS
+            "Fetching closest restaurant in radius of {} meters with filters {:?}",
+            search_radius,
+            filters
         );
-        Restaurant::find()
+        let (min_price, max_price) = filters
+            .price_band
+            .map_or((None, None), |price_band| price_band.bounds(search_config));
+        Ok(Restaurant::find()
             .from_raw_sql(Statement::from_sql_and_values(
                 DbBackend::Postgres,
-                r#"select r.*, m.tg_id manager_tg_id, m.share_contact share_manager_contact from restaurant r 
+                r#"select r.*, m.tg_id manager_tg_id, m.share_contact share_manager_contact,
+                        ST_Distance(r.geo_tag, ST_MakePoint($1, $2)::geography) distance from restaurant r 
                         inner join manager m on r.id = m.restaurant_id and m.tg_id is not null
-                        where ST_DWithin(r.geo_tag, ST_MakePoint($1, $2)::geography, $3) order by score desc, id asc"#,
-                [longitude.into(), latitude.into(), SEARCH_RADIUS_IN_METERS.into()],
+                        where ST_DWithin(r.geo_tag, ST_MakePoint($1, $2)::geography, $3)
+                        and ($4::text is null or lower($4) in
+                            (select lower(trim(kitchen)) from unnest(string_to_array(r.kitchen, ',')) kitchen))
+                        and ($5::text is null or r.segment = $5)
+                        and ($6::integer is null or r.max_price >= $6)
+                        and ($7::integer is null or r.min_price < $7)
+                        order by score desc, id asc"#,
+                [
+                    longitude.into(),
+                    latitude.into(),
+                    search_radius.into(),
+                    filters.kitchen.clone().into(),
+                    filters.segment.clone().into(),
+                    min_price.into(),
+                    max_price.into(),
+                ],
             ))
             .into_model::<RestaurantWithManagerInfo>()
             .all(&self.db)
-            .await
-            .unwrap_or_else(|x| {
-                log::error!("Error while fetching closest restaurant with longtitude = {}, latitude = {} in radius of {} meters: {:?}", longitude, latitude, SEARCH_RADIUS_IN_METERS, x);
-                vec![]
-            })
+            .await?
             .into_iter()
             .filter(|restaurant| restaurant.is_open())
-            .collect()
+            .collect())
     }
 
-    pub async fn find_restaurants_by_ids(&self, ids: Vec<i32>) -> Vec<RestaurantWithManagerInfo> {
+    pub async fn find_restaurants_by_ids(
+        &self,
+        ids: Vec<i32>,
+    ) -> DbResult<Vec<RestaurantWithManagerInfo>> {
         log::info!("Fetching restaurants by ids");
+        Ok(Self::select_restaurants_with_manager_info()
+            .filter(restaurant::Column::Id.is_in(ids))
+            .order_by_desc(restaurant::Column::Score)
+            .order_by_asc(restaurant::Column::Id)
+            .into_model::<RestaurantWithManagerInfo>()
+            .all(&self.db)
+            .await?)
+    }
+
+    /// Same as [`Self::find_restaurants_by_ids`], but also fills in the
+    /// distance in meters from the passed point.
+    pub async fn find_restaurants_by_ids_with_distance(
+        &self,
+        ids: Vec<i32>,
+        longitude: f64,
+        latitude: f64,
+    ) -> DbResult<Vec<RestaurantWithManagerInfo>> {
+        log::info!("Fetching restaurants by ids with distance from the user location");
+        Ok(Self::select_restaurants_with_manager_info()
+            .column_as(
+                Expr::cust_with_values(
+                    r#"ST_Distance("restaurant"."geo_tag", ST_MakePoint($1, $2)::geography)"#,
+                    [longitude, latitude],
+                ),
+                "distance",
+            )
+            .filter(restaurant::Column::Id.is_in(ids))
+            .order_by_desc(restaurant::Column::Score)
+            .order_by_asc(restaurant::Column::Id)
+            .into_model::<RestaurantWithManagerInfo>()
+            .all(&self.db)
+            .await?)
+    }
+
+    fn select_restaurants_with_manager_info() -> Select<Restaurant> {
         Restaurant::find()
             .column_as(
                 Expr::col((Alias::new("m"), manager::Column::TgId)).into_simple_expr(),
@@ -114,58 +253,198 @@ impl DatabaseHandler {
                     }),
                 Alias::new("m"),
             )
-            .filter(restaurant::Column::Id.is_in(ids))
-            .order_by_desc(restaurant::Column::Score)
-            .order_by_asc(restaurant::Column::Id)
-            .into_model::<RestaurantWithManagerInfo>()
-            .all(&self.db)
-            .await
-            .unwrap_or_else(|x| {
-                log::error!("Error while fetching fetching restaurants by ids: {:?}", x);
-                vec![]
-            })
     }
 
-    pub async fn count_restaurants(&self) -> u64 {
-        log::info!("Counting restaurants numnber");
-        Restaurant::find()
-            .count(&self.db)
+    /// Inserts the restaurants or updates the ones with the same external id
+    /// in one transaction and returns their ids. Scores of updated
+    /// restaurants are kept.
+    pub async fn upsert_restaurants(&self, records: &[RestaurantRecord]) -> DbResult<Vec<i32>> {
+        log::info!("Upserting {} restaurants", records.len());
+        let txn = self.db.begin().await?;
+        let mut ids = Vec::with_capacity(records.len());
+        for record in records {
+            let schedule = serde_json::to_value(&record.schedule)
+                .map_err(|err| DbErr::Json(err.to_string()))?;
+            let row = txn
+                .query_one(Statement::from_sql_and_values(
+                    DbBackend::Postgres,
+                    r#"insert into restaurant (external_id, name, maps_url, average_price, segment,
+                            kitchen, phone_number, schedule, geo_tag, time_zone)
+                        values ($1, $2, $3, $4, $5, $6, $7, $8, ST_MakePoint($9, $10)::geography,
+                            $11)
+                        on conflict (external_id) do update set name = excluded.name,
+                            maps_url = excluded.maps_url, average_price = excluded.average_price,
+                            segment = excluded.segment, kitchen = excluded.kitchen,
+                            phone_number = excluded.phone_number, schedule = excluded.schedule,
+                            geo_tag = excluded.geo_tag, time_zone = excluded.time_zone
+                        returning id"#,
+                    [
+                        record.external_id.clone().into(),
+                        record.name.clone().into(),
+                        record.maps_url.clone().into(),
+                        record.average_price.clone().into(),
+                        record.segment.clone().into(),
+                        record.kitchen.clone().into(),
+                        record.phone_number.clone().into(),
+                        schedule.into(),
+                        record.longitude.into(),
+                        record.latitude.into(),
+                        record.time_zone.clone().into(),
+                    ],
+                ))
+                .await?
+                .ok_or(DbErr::RecordNotInserted)?;
+            ids.push(row.try_get("", "id")?);
+        }
+        txn.commit().await?;
+        Ok(ids)
+    }
+
+    pub async fn get_all_restaurant_records(&self) -> DbResult<Vec<RestaurantRecord>> {
+        log::info!("Fetching all restaurant records");
+        Ok(RestaurantRecord::find_by_statement(Statement::from_string(
+            DbBackend::Postgres,
+            r#"select external_id, name, maps_url, average_price, segment, kitchen, phone_number,
+                    ST_X(geo_tag::geometry) longitude, ST_Y(geo_tag::geometry) latitude, time_zone,
+                    schedule
+                from restaurant order by id"#,
+        ))
+        .all(&self.db)
+        .await?)
+    }
+
+    pub async fn find_restaurant_by_external_id(
+        &self,
+        external_id: &str,
+    ) -> DbResult<Option<RestaurantModel>> {
+        log::info!("Fetching restaurant by external_id = {}", external_id);
+        Ok(Restaurant::find()
+            .filter(restaurant::Column::ExternalId.eq(external_id))
+            .one(&self.db)
+            .await?)
+    }
+
+    /// Subscribes to the notifications sent to `channel` with `pg_notify`.
+    pub async fn listen(&self, channel: &str) -> DbResult<NotificationListener> {
+        let mut listener = PgListener::connect_with(self.db.get_postgres_connection_pool())
             .await
-            .unwrap_or_else(|x| {
-                log::error!("Error while counting restaurants number: {:?}", x);
-                0
-            })
+            .map_err(runtime_error)?;
+        listener.listen(channel).await.map_err(runtime_error)?;
+        Ok(NotificationListener(listener))
     }
 
-    pub async fn find_manager_by_token(&self, token: String) -> Option<ManagerModel> {
+    pub async fn find_manager_by_token_hash(
+        &self,
+        token_hash: String,
+    ) -> DbResult<Option<ManagerModel>> {
         log::info!("Fetching manager by token");
-        Manager::find()
-            .filter(manager::Column::Token.eq(token))
+        Ok(Manager::find()
+            .filter(manager::Column::TokenHash.eq(token_hash))
             .one(&self.db)
-            .await
-            .unwrap_or_else(|x| {
-                log::error!("Error while fetching manager by token: {:?}", x);
-                None
-            })
+            .await?)
+    }
+
+    /// Adds a manager who is yet to sign in with the token.
+    pub async fn issue_manager_token(
+        &self,
+        restaurant_id: i32,
+        token_hash: String,
+        expires_at: DateTime<Local>,
+    ) -> DbResult<i32> {
+        log::info!(
+            "Issuing manager token for restaurant with id = {}",
+            restaurant_id
+        );
+        Ok(insert_manager(&self.db, restaurant_id, token_hash, expires_at).await?)
+    }
+
+    /// Removes the managers of the restaurant, signed in or not, and returns
+    /// their ids.
+    pub async fn revoke_manager_tokens(&self, restaurant_id: i32) -> DbResult<Vec<i32>> {
+        log::info!(
+            "Revoking manager tokens of restaurant with id = {}",
+            restaurant_id
+        );
+        Ok(delete_managers(&self.db, restaurant_id).await?)
+    }
+
+    /// Revokes the manager tokens of the restaurant and issues a new one in
+    /// one transaction. Returns the new manager id and the revoked ones.
+    pub async fn rotate_manager_tokens(
+        &self,
+        restaurant_id: i32,
+        token_hash: String,
+        expires_at: DateTime<Local>,
+    ) -> DbResult<(i32, Vec<i32>)> {
+        log::info!(
+            "Rotating manager tokens of restaurant with id = {}",
+            restaurant_id
+        );
+        let txn = self.db.begin().await?;
+        let revoked_ids = delete_managers(&txn, restaurant_id).await?;
+        let manager_id = insert_manager(&txn, restaurant_id, token_hash, expires_at).await?;
+        txn.commit().await?;
+        Ok((manager_id, revoked_ids))
+    }
+
+    /// Binds the manager to the Telegram user unless the token was redeemed
+    /// concurrently, has expired or another manager of the restaurant has
+    /// signed in. Returns whether it was bound.
+    pub async fn redeem_manager_token(&self, manager_id: i32, tg_id: i64) -> DbResult<bool> {
+        log::info!("Redeeming token of manager with id = {}", manager_id);
+        let now = Local::now().fixed_offset();
+        let result = Manager::update_many()
+            .col_expr(manager::Column::TgId, Expr::value(tg_id))
+            .col_expr(manager::Column::TokenUsedAt, Expr::value(now))
+            .filter(manager::Column::Id.eq(manager_id))
+            .filter(manager::Column::TokenUsedAt.is_null())
+            .filter(
+                manager::Column::TokenExpiresAt
+                    .is_null()
+                    .or(manager::Column::TokenExpiresAt.gt(now)),
+            )
+            .filter(Expr::cust(
+                "not exists (select 1 from manager signed_in where signed_in.restaurant_id = \
+                 manager.restaurant_id and signed_in.tg_id is not null)",
+            ))
+            .exec(&self.db)
+            .await?;
+        Ok(result.rows_affected == 1)
+    }
+
+    pub async fn has_signed_in_manager(&self, restaurant_id: i32) -> DbResult<bool> {
+        log::info!(
+            "Checking for a signed-in manager of restaurant with id = {}",
+            restaurant_id
+        );
+        Ok(Manager::find()
+            .filter(manager::Column::RestaurantId.eq(restaurant_id))
+            .filter(manager::Column::TgId.is_not_null())
+            .one(&self.db)
+            .await?
+            .is_some())
     }
 
-    pub async fn find_manager_by_tg_id(&self, id: i64) -> Option<ManagerModel> {
+    pub async fn find_manager_by_tg_id(&self, id: i64) -> DbResult<Option<ManagerModel>> {
         log::info!("Fetching manager with tg_id = {}", id);
-        Manager::find()
+        Ok(Manager::find()
             .filter(manager::Column::TgId.eq(id))
             .one(&self.db)
+            .await?)
+    }
+
+    /// Removes the manager row, so that its token cannot be used again.
+    /// Returns `false` if it was already removed.
+    pub async fn delete_manager(&self, id: i32) -> DbResult<bool> {
+        log::info!("Deleting manager with id = {}", id);
+        Manager::delete_by_id(id)
+            .exec(&self.db)
             .await
-            .unwrap_or_else(|x| {
-                log::error!("Error while fetching manager with tg_id = {}: {:?}", id, x);
-                None
-            })
+            .map(|result| result.rows_affected > 0)
+            .map_err(DatabaseError::from)
     }
 
-    pub async fn update_restaurant_score_wiht_raw_sql(
-        &self,
-        id: i32,
-        score: i32,
-    ) -> Result<ExecResult, DbErr> {
+    pub async fn update_restaurant_score_wiht_raw_sql(&self, id: i32, score: i32) -> DbResult<()> {
         log::info!("Set score = {} for restaurant with id = {}", score, id);
         self.db
             .execute(Statement::from_sql_and_values(
@@ -173,10 +452,620 @@ impl DatabaseHandler {
                 "Update restaurant set score = $1 where id = $2",
                 [score.into(), id.into()],
             ))
+            .await?;
+        Ok(())
+    }
+
+    pub async fn update_manager(&self, manager: ManagerActiveModel) -> DbResult<ManagerModel> {
+        Ok(manager.update(&self.db).await?)
+    }
+
+    pub async fn get_all_booking_notifications(&self) -> DbResult<Vec<BookingNotificationModel>> {
+        log::info!("Fetching all booking notifications");
+        Ok(BookingNotification::find().all(&self.db).await?)
+    }
+
+    pub async fn save_booking_notification(
+        &self,
+        restaurant_id: i32,
+        person_number: u8,
+        expires_at: DateTime<Local>,
+    ) -> DbResult<()> {
+        log::info!(
+            "Save booking notification for {} persons for restaurant with id = {}",
+            person_number,
+            restaurant_id
+        );
+        BookingNotification::insert(booking_notification::ActiveModel {
+            restaurant_id: Set(restaurant_id),
+            person_number: Set(person_number as i16),
+            expires_at: Set(expires_at.fixed_offset()),
+            message_id: Set(None),
+        })
+        .on_conflict(
+            OnConflict::columns([
+                booking_notification::Column::RestaurantId,
+                booking_notification::Column::PersonNumber,
+            ])
+            .update_columns([
+                booking_notification::Column::ExpiresAt,
+                booking_notification::Column::MessageId,
+            ])
+            .to_owned(),
+        )
+        .exec(&self.db)
+        .await?;
+        Ok(())
+    }
+
+    pub async fn find_booking_notification(
+        &self,
+        restaurant_id: i32,
+        person_number: u8,
+    ) -> DbResult<Option<BookingNotificationModel>> {
+        log::info!(
+            "Fetching booking notification for {} persons for restaurant with id = {}",
+            person_number,
+            restaurant_id
+        );
+        Ok(
+            BookingNotification::find_by_id((restaurant_id, person_number as i16))
+                .one(&self.db)
+                .await?,
+        )
+    }
+
+    pub async fn set_booking_notification_message_id(
+        &self,
+        restaurant_id: i32,
+        person_number: u8,
+        message_id: i32,
+    ) -> DbResult<()> {
+        BookingNotification::update_many()
+            .col_expr(
+                booking_notification::Column::MessageId,
+                Expr::value(message_id),
+            )
+            .filter(booking_notification::Column::RestaurantId.eq(restaurant_id))
+            .filter(booking_notification::Column::PersonNumber.eq(person_number as i16))
+            .exec(&self.db)
+            .await?;
+        Ok(())
+    }
+
+    pub async fn delete_booking_notification(
+        &self,
+        restaurant_id: i32,
+        person_number: u8,
+    ) -> DbResult<()> {
+        log::info!(
+            "Delete booking notification for {} persons for restaurant with id = {}",
+            person_number,
+            restaurant_id
+        );
+        BookingNotification::delete_by_id((restaurant_id, person_number as i16))
+            .exec(&self.db)
+            .await?;
+        Ok(())
+    }
+
+    pub async fn get_all_booking_holds(&self) -> DbResult<Vec<BookingHoldModel>> {
+        log::info!("Fetching all booking holds");
+        Ok(BookingHold::find().all(&self.db).await?)
+    }
+
+    pub async fn save_booking_hold(
+        &self,
+        restaurant_id: i32,
+        person_number: u8,
+        expires_at: DateTime<Local>,
+    ) -> DbResult<()> {
+        log::info!(
+            "Save booking hold for {} persons for restaurant with id = {}",
+            person_number,
+            restaurant_id
+        );
+        BookingHold::insert(booking_hold::ActiveModel {
+            restaurant_id: Set(restaurant_id),
+            person_number: Set(person_number as i16),
+            expires_at: Set(expires_at.fixed_offset()),
+        })
+        .on_conflict(
+            OnConflict::columns([
+                booking_hold::Column::RestaurantId,
+                booking_hold::Column::PersonNumber,
+            ])
+            .update_column(booking_hold::Column::ExpiresAt)
+            .to_owned(),
+        )
+        .exec(&self.db)
+        .await?;
+        Ok(())
+    }
+
+    pub async fn delete_booking_hold(&self, restaurant_id: i32, person_number: u8) -> DbResult<()> {
+        log::info!(
+            "Delete booking hold for {} persons for restaurant with id = {}",
+            person_number,
+            restaurant_id
+        );
+        BookingHold::delete_by_id((restaurant_id, person_number as i16))
+            .exec(&self.db)
+            .await?;
+        Ok(())
+    }
+
+    pub async fn create_booking_request(
+        &self,
+        user_chat_id: i64,
+        person_number: u8,
+        longitude: f64,
+        latitude: f64,
+        search_radius: u16,
+        filters: SearchFilters,
+    ) -> DbResult<BookingRequestModel> {
+        log::info!(
+            "Create booking request for {} persons from chat with id = {} in radius of {} meters",
+            person_number,
+            user_chat_id,
+            search_radius
+        );
+        Ok(booking_request::ActiveModel {
+            user_chat_id: Set(user_chat_id),
+            person_number: Set(person_number as i16),
+            longitude: Set(longitude),
+            latitude: Set(latitude),
+            search_radius: Set(search_radius as i32),
+            kitchen_filter: Set(filters.kitchen),
+            segment_filter: Set(filters.segment),
+            price_band_filter: Set(filters.price_band),
+            created_at: Set(Local::now().fixed_offset()),
+            status: Set(BookingRequestStatus::Pending),
+            ..Default::default()
+        }
+        .insert(&self.db)
+        .await?)
+    }
+
+    pub async fn find_booking_request_by_id(
+        &self,
+        id: i32,
+    ) -> DbResult<Option<BookingRequestModel>> {
+        log::info!("Fetching booking request by id = {}", id);
+        Ok(BookingRequest::find_by_id(id).one(&self.db).await?)
+    }
+
+    pub async fn find_pending_booking_request_by_user_chat_id(
+        &self,
+        user_chat_id: i64,
+    ) -> DbResult<Option<BookingRequestModel>> {
+        log::info!(
+            "Fetching pending booking request of chat with id = {}",
+            user_chat_id
+        );
+        Ok(BookingRequest::find()
+            .filter(booking_request::Column::UserChatId.eq(user_chat_id))
+            .filter(booking_request::Column::Status.eq(BookingRequestStatus::Pending))
+            .order_by_desc(booking_request::Column::CreatedAt)
+            .one(&self.db)
+            .await?)
+    }
+
+    /// Moves a pending booking request to `status`. Returns `false` if the
+    /// request was already completed, e.g. cancelled while being awaited.
+    pub async fn complete_pending_booking_request(
+        &self,
+        id: i32,
+        status: BookingRequestStatus,
+    ) -> DbResult<bool> {
+        log::info!(
+            "Set status = {:?} for booking request with id = {}",
+            status,
+            id
+        );
+        BookingRequest::update_many()
+            .col_expr(booking_request::Column::Status, Expr::value(status))
+            .filter(booking_request::Column::Id.eq(id))
+            .filter(booking_request::Column::Status.eq(BookingRequestStatus::Pending))
+            .exec(&self.db)
+            .await
+            .map(|update_result| update_result.rows_affected > 0)
+            .map_err(DatabaseError::from)
+    }
+
+    /// Widens the search radius of a pending booking request. Returns `false`
+    /// if the request was already completed.
+    pub async fn expand_pending_booking_request_search_radius(
+        &self,
+        id: i32,
+        search_radius: u16,
+    ) -> DbResult<bool> {
+        log::info!(
+            "Set search radius = {} meters for booking request with id = {}",
+            search_radius,
+            id
+        );
+        BookingRequest::update_many()
+            .col_expr(
+                booking_request::Column::SearchRadius,
+                Expr::value(search_radius as i32),
+            )
+            .filter(booking_request::Column::Id.eq(id))
+            .filter(booking_request::Column::Status.eq(BookingRequestStatus::Pending))
+            .exec(&self.db)
+            .await
+            .map(|update_result| update_result.rows_affected > 0)
+            .map_err(DatabaseError::from)
+    }
+
+    /// Marks an answered booking request as fulfilled. Returns `false` if it
+    /// was not answered anymore, e.g. the restaurant was already selected.
+    pub async fn fulfill_answered_booking_request(&self, id: i32) -> DbResult<bool> {
+        log::info!(
+            "Set status = {:?} for booking request with id = {}",
+            BookingRequestStatus::Fulfilled,
+            id
+        );
+        BookingRequest::update_many()
+            .col_expr(
+                booking_request::Column::Status,
+                Expr::value(BookingRequestStatus::Fulfilled),
+            )
+            .filter(booking_request::Column::Id.eq(id))
+            .filter(booking_request::Column::Status.eq(BookingRequestStatus::Answered))
+            .exec(&self.db)
+            .await
+            .map(|update_result| update_result.rows_affected == 1)
+            .map_err(DatabaseError::from)
+    }
+
+    pub async fn create_booking_request_answer(
+        &self,
+        booking_request_id: i32,
+        restaurant_id: i32,
+        approved: Option<bool>,
+    ) -> DbResult<()> {
+        log::info!(
+            "Create answer of restaurant with id = {} for booking request with id = {}",
+            restaurant_id,
+            booking_request_id
+        );
+        BookingRequestAnswer::insert(booking_request_answer::ActiveModel {
+            booking_request_id: Set(booking_request_id),
+            restaurant_id: Set(restaurant_id),
+            approved: Set(approved),
+            answered_at: Set(approved.map(|_| Local::now().fixed_offset())),
+        })
+        .on_conflict(
+            OnConflict::columns([
+                booking_request_answer::Column::BookingRequestId,
+                booking_request_answer::Column::RestaurantId,
+            ])
+            .do_nothing()
+            .to_owned(),
+        )
+        .exec_without_returning(&self.db)
+        .await?;
+        Ok(())
+    }
+
+    pub async fn find_booking_request_answer(
+        &self,
+        booking_request_id: i32,
+        restaurant_id: i32,
+    ) -> DbResult<Option<BookingRequestAnswerModel>> {
+        log::info!(
+            "Fetching answer of restaurant with id = {} for booking request with id = {}",
+            restaurant_id,
+            booking_request_id
+        );
+        Ok(
+            BookingRequestAnswer::find_by_id((booking_request_id, restaurant_id))
+                .one(&self.db)
+                .await?,
+        )
+    }
+
+    pub async fn find_booking_request_answers(
+        &self,
+        booking_request_id: i32,
+    ) -> DbResult<Vec<BookingRequestAnswerModel>> {
+        log::info!(
+            "Fetching answers for booking request with id = {}",
+            booking_request_id
+        );
+        Ok(BookingRequestAnswer::find()
+            .filter(booking_request_answer::Column::BookingRequestId.eq(booking_request_id))
+            .all(&self.db)
+            .await?)
+    }
+
+    /// Applies a manager answer to every pending booking request for
+    /// `person_number` persons that is still awaiting this restaurant.
+    /// Returns ids of the booking requests that got answered.
+    pub async fn answer_pending_booking_requests(
+        &self,
+        restaurant_id: i32,
+        person_number: u8,
+        approved: bool,
+    ) -> DbResult<Vec<i32>> {
+        log::info!(
+            "Answer pending booking requests for {} persons of restaurant with id = {}",
+            person_number,
+            restaurant_id
+        );
+        self.db
+            .query_all(Statement::from_sql_and_values(
+                DbBackend::Postgres,
+                r#"update booking_request_answer a set approved = $1, answered_at = now() from booking_request r
+                        where a.booking_request_id = r.id and a.restaurant_id = $2 and a.approved is null
+                        and r.person_number = $3 and r.status = $4 returning a.booking_request_id"#,
+                [
+                    approved.into(),
+                    restaurant_id.into(),
+                    (person_number as i16).into(),
+                    BookingRequestStatus::Pending.into(),
+                ],
+            ))
+            .await?
+            .into_iter()
+            .map(|row| row.try_get::<i32>("", "booking_request_id"))
+            .collect::<Result<Vec<i32>, DbErr>>()
+            .map_err(DatabaseError::from)
+    }
+
+    /// Counts pending booking requests for `person_number` persons that are
+    /// still awaiting an answer of the restaurant.
+    pub async fn count_awaiting_booking_request_answers(
+        &self,
+        restaurant_id: i32,
+        person_number: u8,
+    ) -> DbResult<i64> {
+        self.db
+            .query_one(Statement::from_sql_and_values(
+                DbBackend::Postgres,
+                r#"select count(*) awaiting from booking_request_answer a inner join booking_request r on a.booking_request_id = r.id
+                        where a.restaurant_id = $1 and a.approved is null and r.person_number = $2 and r.status = $3"#,
+                [
+                    restaurant_id.into(),
+                    (person_number as i16).into(),
+                    BookingRequestStatus::Pending.into(),
+                ],
+            ))
+            .await?
+            .map_or(Ok(0), |row| row.try_get::<i64>("", "awaiting"))
+            .map_err(DatabaseError::from)
+    }
+
+    /// Checks whether a pending or answered booking request for
+    /// `person_number` persons is approved by the restaurant. An approval
+    /// applies to all such requests, so its hold is kept while one remains.
+    pub async fn has_approved_open_booking_requests(
+        &self,
+        restaurant_id: i32,
+        person_number: u8,
+    ) -> DbResult<bool> {
+        self.db
+            .query_one(Statement::from_sql_and_values(
+                DbBackend::Postgres,
+                r#"select exists(select 1 from booking_request_answer a inner join booking_request r on a.booking_request_id = r.id
+                        where a.restaurant_id = $1 and a.approved and r.person_number = $2 and r.status in ($3, $4)) approved"#,
+                [
+                    restaurant_id.into(),
+                    (person_number as i16).into(),
+                    BookingRequestStatus::Pending.into(),
+                    BookingRequestStatus::Answered.into(),
+                ],
+            ))
+            .await?
+            .map_or(Ok(false), |row| row.try_get::<bool>("", "approved"))
+            .map_err(DatabaseError::from)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        config::SearchConfig,
+        db::{DatabaseError, DatabaseHandler},
+        entity::{
+            booking_request::{BookingRequestStatus, PriceBand},
+            restaurant::{Schedule, WorkingTime},
+        },
+        model::{restaurant_record::RestaurantRecord, search_filters::SearchFilters},
+    };
+    use chrono::{Duration, Local, NaiveTime};
+    use rand::Rng;
+    use sea_orm::{ConnAcquireErr, ConnectionTrait, DbBackend, DbErr, RuntimeErr, Statement};
+    use std::env;
+
+    #[test]
+    fn connection_errors_make_database_unavailable() {
+        let err = DatabaseError::from(DbErr::ConnectionAcquire(ConnAcquireErr::Timeout));
+        assert!(matches!(err, DatabaseError::Unavailable(_)));
+
+        let err = DatabaseError::from(DbErr::Conn(RuntimeErr::Internal("refused".to_owned())));
+        assert!(matches!(err, DatabaseError::Unavailable(_)));
+    }
+
+    #[test]
+    fn other_errors_are_query_errors() {
+        let err = DatabaseError::from(DbErr::RecordNotFound("restaurant".to_owned()));
+        assert!(matches!(err, DatabaseError::Query(_)));
+    }
+
+    /// Adds a restaurant with a signed-in manager around a random location
+    /// and returns its id and coordinates.
+    async fn create_restaurant(db_handler: &DatabaseHandler) -> (i32, f64, f64) {
+        let (longitude, latitude, suffix) = {
+            let mut rng = rand::thread_rng();
+            (
+                rng.gen_range(-170.0..170.0),
+                rng.gen_range(-60.0..60.0),
+                rng.gen::<u32>(),
+            )
+        };
+        let record = RestaurantRecord {
+            external_id: format!("db-test-{suffix}"),
+            name: "Filters".to_owned(),
+            maps_url: "https://maps.example".to_owned(),
+            average_price: "700–1 500 ₽".to_owned(),
+            segment: "₽₽".to_owned(),
+            kitchen: "Европейская, Русская".to_owned(),
+            phone_number: "+70000000000".to_owned(),
+            longitude,
+            latitude,
+            time_zone: "Europe/Moscow".to_owned(),
+            schedule: Schedule::Regular {
+                working_time: WorkingTime {
+                    start_time: NaiveTime::MIN,
+                    end_time: NaiveTime::from_hms_nano_opt(23, 59, 59, 999_999_999).unwrap(),
+                },
+            },
+        };
+        let id = db_handler.upsert_restaurants(&[record]).await.unwrap()[0];
+        let manager_id = db_handler
+            .issue_manager_token(id, suffix.to_string(), Local::now() + Duration::hours(1))
+            .await
+            .unwrap();
+        db_handler
+            .redeem_manager_token(manager_id, suffix.into())
+            .await
+            .unwrap();
+        (id, longitude, latitude)
+    }
+
+    async fn execute(db_handler: &DatabaseHandler, sql: &str, id: i32) {
+        db_handler
+            .db
+            .execute(Statement::from_sql_and_values(
+                DbBackend::Postgres,
+                sql,
+                [id.into()],
+            ))
             .await
+            .unwrap();
+    }
+
+    async fn delete_restaurant(db_handler: &DatabaseHandler, id: i32) {
+        execute(
+            db_handler,
+            "delete from manager where restaurant_id = $1",
+            id,
+        )
+        .await;
+        execute(db_handler, "delete from restaurant where id = $1", id).await;
+    }
+
+    /// Needs `TEST_DATABASE_URL` pointing to a database with the migrations
+    /// applied.
+    #[tokio::test]
+    #[ignore = "needs TEST_DATABASE_URL"]
+    async fn filters_match_whole_kitchens_and_price_ranges() {
+        let url = env::var("TEST_DATABASE_URL").expect("TEST_DATABASE_URL is not set");
+        let db_handler = DatabaseHandler::new(url).await.unwrap();
+        let (id, longitude, latitude) = create_restaurant(&db_handler).await;
+        let search_config = SearchConfig::default();
+        let finds = |filters: SearchFilters| {
+            let db_handler = &db_handler;
+            let search_config = &search_config;
+            async move {
+                db_handler
+                    .find_closest_restaurants(longitude, latitude, 100, &filters, search_config)
+                    .await
+                    .unwrap()
+                    .iter()
+                    .any(|restaurant| restaurant.id == id)
+            }
+        };
+        let kitchen = |kitchen: &str| SearchFilters {
+            kitchen: Some(kitchen.to_owned()),
+            ..Default::default()
+        };
+        let price_band = |price_band| SearchFilters {
+            price_band: Some(price_band),
+            ..Default::default()
+        };
+
+        let results = [
+            finds(kitchen("Русская")).await,
+            finds(kitchen("Европ")).await,
+            finds(kitchen("%")).await,
+            finds(price_band(PriceBand::Low)).await,
+            finds(price_band(PriceBand::Medium)).await,
+            finds(price_band(PriceBand::High)).await,
+        ];
+        delete_restaurant(&db_handler, id).await;
+
+        assert_eq!(results, [true, false, false, true, true, false]);
     }
 
-    pub async fn update_manager(&self, manager: ManagerActiveModel) -> Result<ManagerModel, DbErr> {
-        manager.update(&self.db).await
+    /// Needs `TEST_DATABASE_URL` pointing to a database with the migrations
+    /// applied.
+    #[tokio::test]
+    #[ignore = "needs TEST_DATABASE_URL"]
+    async fn approval_is_shared_by_open_booking_requests() {
+        let url = env::var("TEST_DATABASE_URL").expect("TEST_DATABASE_URL is not set");
+        let db_handler = DatabaseHandler::new(url).await.unwrap();
+        let (id, longitude, latitude) = create_restaurant(&db_handler).await;
+        let mut booking_request_ids = Vec::new();
+        for _ in 0..2 {
+            let booking_request = db_handler
+                .create_booking_request(
+                    rand::thread_rng().gen_range(1_000_000..i64::MAX / 2),
+                    2,
+                    longitude,
+                    latitude,
+                    500,
+                    SearchFilters::default(),
+                )
+                .await
+                .unwrap();
+            db_handler
+                .create_booking_request_answer(booking_request.id, id, None)
+                .await
+                .unwrap();
+            booking_request_ids.push(booking_request.id);
+        }
+
+        db_handler
+            .answer_pending_booking_requests(id, 2, true)
+            .await
+            .unwrap();
+        db_handler
+            .complete_pending_booking_request(
+                booking_request_ids[0],
+                BookingRequestStatus::Answered,
+            )
+            .await
+            .unwrap();
+        db_handler
+            .fulfill_answered_booking_request(booking_request_ids[0])
+            .await
+            .unwrap();
+        let shared = db_handler
+            .has_approved_open_booking_requests(id, 2)
+            .await
+            .unwrap();
+        db_handler
+            .complete_pending_booking_request(booking_request_ids[1], BookingRequestStatus::Expired)
+            .await
+            .unwrap();
+        let released = !db_handler
+            .has_approved_open_booking_requests(id, 2)
+            .await
+            .unwrap();
+        for booking_request_id in booking_request_ids {
+            execute(
+                &db_handler,
+                "delete from booking_request where id = $1",
+                booking_request_id,
+            )
+            .await;
+        }
+        delete_restaurant(&db_handler, id).await;
+
+        assert!(shared);
+        assert!(released);
     }
 }
@@ -0,0 +1,77 @@
+//! Behaviour every dialogue storage backend has to share.
+
+use crate::model::{search_filters::SearchFilters, state::State};
+use rand::Rng;
+use std::{fmt::Debug, sync::Arc};
+use teloxide::{dispatching::dialogue::Storage, prelude::ChatId};
+
+/// Runs the suite against `storage`. Chat ids are random, so backends on a
+/// shared server may be checked concurrently.
+pub(crate) async fn check_storage<St>(storage: Arc<St>)
+where
+    St: Storage<State> + ?Sized,
+    St::Error: Debug,
+{
+    let (chat_id, other_chat_id) = {
+        let mut rng = rand::thread_rng();
+        let chat_id = rng.gen_range(1..i64::MAX / 2);
+        (ChatId(chat_id), ChatId(-chat_id))
+    };
+    let state = State::ReceiveLocation {
+        person_number: 4,
+        search_radius: 1000,
+        filters: SearchFilters {
+            kitchen: Some("Грузинская".to_owned()),
+            ..SearchFilters::default()
+        },
+    };
+
+    let missing = storage.clone().get_dialogue(chat_id).await.unwrap();
+    assert_eq!(missing, None);
+
+    storage
+        .clone()
+        .update_dialogue(chat_id, State::ReceivePersonNumber)
+        .await
+        .unwrap();
+    storage
+        .clone()
+        .update_dialogue(other_chat_id, State::WaitingForRequests)
+        .await
+        .unwrap();
+    let inserted = storage.clone().get_dialogue(chat_id).await.unwrap();
+    assert_eq!(inserted, Some(State::ReceivePersonNumber));
+
+    storage
+        .clone()
+        .update_dialogue(chat_id, state.clone())
+        .await
+        .unwrap();
+    let updated = storage.clone().get_dialogue(chat_id).await.unwrap();
+    assert_eq!(updated, Some(state));
+
+    storage.clone().remove_dialogue(chat_id).await.unwrap();
+    let removed = storage.clone().get_dialogue(chat_id).await.unwrap();
+    assert_eq!(removed, None);
+    assert!(storage.clone().remove_dialogue(chat_id).await.is_err());
+
+    let other = storage.clone().get_dialogue(other_chat_id).await.unwrap();
+    assert_eq!(other, Some(State::WaitingForRequests));
+    storage.remove_dialogue(other_chat_id).await.unwrap();
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::dialogue_storage::conformance::check_storage;
+    use teloxide::dispatching::dialogue::{InMemStorage, Storage};
+
+    #[tokio::test]
+    async fn in_memory_storage_conforms() {
+        check_storage(InMemStorage::new()).await;
+    }
+
+    #[tokio::test]
+    async fn erased_storage_conforms() {
+        check_storage(InMemStorage::new().erase()).await;
+    }
+}
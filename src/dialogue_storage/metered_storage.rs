@@ -0,0 +1,144 @@
+use crate::{
+    dialogue_storage::{skytable_storage::SkytableStorageError, sql_storage::SqlStorageError},
+    model::state::State,
+    monitoring::metrics,
+};
+use futures::future::BoxFuture;
+use std::{error::Error, sync::Arc};
+use teloxide::{
+    dispatching::dialogue::{
+        serializer::{Bincode, Serializer},
+        ErasedStorage, InMemStorageError, Storage,
+    },
+    prelude::ChatId,
+};
+
+/// The persistent backends are always opened with [`Bincode`], see
+/// `open_dialogue_storage`.
+type SerializerError = <Bincode as Serializer<State>>::Error;
+
+/// Counts failed operations of the wrapped storage, whatever its backend is.
+pub struct MeteredStorage<D> {
+    storage: Arc<ErasedStorage<D>>,
+}
+
+impl<D> MeteredStorage<D> {
+    pub fn new(storage: Arc<ErasedStorage<D>>) -> Arc<Self> {
+        Arc::new(Self { storage })
+    }
+}
+
+/// Removing a dialogue which was never stored, e.g. on `/reset`, is not a
+/// failure of the storage.
+fn is_dialogue_not_found(err: &(dyn Error + Send + Sync + 'static)) -> bool {
+    matches!(
+        err.downcast_ref::<InMemStorageError>(),
+        Some(InMemStorageError::DialogueNotFound)
+    ) || matches!(
+        err.downcast_ref::<SqlStorageError<SerializerError>>(),
+        Some(SqlStorageError::DialogueNotFound)
+    ) || matches!(
+        err.downcast_ref::<SkytableStorageError<SerializerError>>(),
+        Some(SkytableStorageError::DialogueNotFound)
+    )
+}
+
+fn record_error<T>(
+    operation: &'static str,
+    result: Result<T, Box<dyn Error + Send + Sync>>,
+) -> Result<T, Box<dyn Error + Send + Sync>> {
+    if let Err(err) = &result {
+        if !is_dialogue_not_found(err.as_ref()) {
+            metrics::record_dialogue_storage_error(operation);
+        }
+    }
+    result
+}
+
+impl<D> Storage<D> for MeteredStorage<D>
+where
+    D: Send + 'static,
+{
+    type Error = Box<dyn Error + Send + Sync>;
+
+    fn remove_dialogue(
+        self: Arc<Self>,
+        chat_id: ChatId,
+    ) -> BoxFuture<'static, Result<(), Self::Error>> {
+        Box::pin(async move {
+            record_error(
+                "remove",
+                self.storage.clone().remove_dialogue(chat_id).await,
+            )
+        })
+    }
+
+    fn update_dialogue(
+        self: Arc<Self>,
+        chat_id: ChatId,
+        dialogue: D,
+    ) -> BoxFuture<'static, Result<(), Self::Error>> {
+        Box::pin(async move {
+            record_error(
+                "update",
+                self.storage
+                    .clone()
+                    .update_dialogue(chat_id, dialogue)
+                    .await,
+            )
+        })
+    }
+
+    fn get_dialogue(
+        self: Arc<Self>,
+        chat_id: ChatId,
+    ) -> BoxFuture<'static, Result<Option<D>, Self::Error>> {
+        Box::pin(
+            async move { record_error("get", self.storage.clone().get_dialogue(chat_id).await) },
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        dialogue_storage::{
+            conformance::check_storage,
+            metered_storage::{record_error, MeteredStorage},
+            sql_storage::SqlStorage,
+        },
+        model::state::State,
+        monitoring::metrics,
+    };
+    use sea_orm::Database;
+    use std::io;
+    use teloxide::{
+        dispatching::dialogue::{serializer::Bincode, InMemStorage, Storage},
+        prelude::ChatId,
+    };
+
+    #[tokio::test]
+    async fn metered_storage_conforms_and_skips_missing_dialogues() {
+        let storage = MeteredStorage::new(InMemStorage::<State>::new().erase());
+
+        check_storage(storage.clone()).await;
+        assert!(storage.remove_dialogue(ChatId(1)).await.is_err());
+        let db = Database::connect("sqlite::memory:").await.unwrap();
+        let storage =
+            MeteredStorage::<State>::new(SqlStorage::open(db, Bincode).await.unwrap().erase());
+        assert!(storage.remove_dialogue(ChatId(1)).await.is_err());
+
+        assert!(!metrics::render()
+            .contains("mest_net_dialogue_storage_errors_total{operation=\"remove\"}"));
+    }
+
+    #[test]
+    fn storage_failures_are_counted() {
+        let result: Result<(), _> = record_error("get", Err(io::Error::other("offline").into()));
+
+        assert!(result.is_err());
+        assert!(
+            metrics::render().contains("mest_net_dialogue_storage_errors_total{operation=\"get\"}")
+        );
+    }
+}
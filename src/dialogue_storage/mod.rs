@@ -1 +1,87 @@
+#[cfg(test)]
+mod conformance;
+pub(crate) mod metered_storage;
 pub(crate) mod skytable_storage;
+pub(crate) mod sql_storage;
+
+use crate::{
+    config::{AppConfig, DialogueStorageBackend},
+    model::state::State,
+    monitoring::health::HealthChecks,
+};
+use anyhow::{Context, Result};
+use metered_storage::MeteredStorage;
+use sea_orm::{Database, DatabaseConnection};
+use secrecy::ExposeSecret;
+use skytable_storage::{PoolOptions, SkytableStorage};
+use sql_storage::SqlStorage;
+use std::{sync::Arc, time::Duration};
+use teloxide::dispatching::dialogue::{serializer::Bincode, ErasedStorage, InMemStorage, Storage};
+
+/// Opens the dialogue storage selected in the config. The Postgres backend
+/// shares `db` with the rest of the bot. Storage errors are counted in the
+/// metrics, and the SQLite and Skytable backends are added to the readiness
+/// checks.
+pub(crate) async fn open_dialogue_storage(
+    config: &AppConfig,
+    db: DatabaseConnection,
+    health_checks: &mut HealthChecks,
+) -> Result<Arc<ErasedStorage<State>>> {
+    let storage = match config.dialogue_storage.backend {
+        DialogueStorageBackend::Memory => InMemStorage::new().erase(),
+        DialogueStorageBackend::Sqlite => {
+            let url = format!(
+                "sqlite://{}?mode=rwc",
+                config.dialogue_storage.sqlite_path.display()
+            );
+            let db = Database::connect(url)
+                .await
+                .context("failed to open SQLite dialogue database")?;
+            let storage = SqlStorage::open(db, Bincode)
+                .await
+                .context("failed to open SQLite dialogue storage")?;
+            let checked_storage = storage.clone();
+            health_checks.add("sqlite", move || {
+                let storage = checked_storage.clone();
+                async move { storage.ping().await }
+            });
+            storage.erase()
+        }
+        DialogueStorageBackend::Postgres => SqlStorage::open(db, Bincode)
+            .await
+            .context("failed to open Postgres dialogue storage")?
+            .erase(),
+        DialogueStorageBackend::Skytable => {
+            let skytable_config = config
+                .skytable
+                .as_ref()
+                .context("skytable section is required by the Skytable dialogue storage")?;
+            let storage = SkytableStorage::open(
+                &skytable_config.host,
+                skytable_config.port,
+                &skytable_config.user,
+                skytable_config.password.expose_secret(),
+                PoolOptions {
+                    max_connections: skytable_config.max_connections,
+                    connection_timeout: Duration::from_millis(
+                        skytable_config.connection_timeout_ms,
+                    ),
+                    retry_attempts: skytable_config.retry_attempts,
+                    retry_initial_backoff: Duration::from_millis(
+                        skytable_config.retry_initial_backoff_ms,
+                    ),
+                },
+                Bincode,
+            )
+            .await
+            .context("failed to open Skytable dialogue storage")?;
+            let checked_storage = storage.clone();
+            health_checks.add("skytable", move || {
+                let storage = checked_storage.clone();
+                async move { storage.ping().await }
+            });
+            storage.erase()
+        }
+    };
+    Ok(MeteredStorage::new(storage))
+}
@@ -1,11 +1,15 @@
+use async_std::task;
 use bb8::RunError;
 use futures::future::BoxFuture;
 use serde::{de::DeserializeOwned, Serialize};
-use skytable::{error::Error, pool, pool::ConnectionMgrTcp, query, Config};
+use skytable::{
+    error::Error, pool::ConnectionMgrTcp, query, query::Query, response::FromResponse, Config,
+};
 use std::{
     convert::Infallible,
     fmt::{Debug, Display},
     sync::Arc,
+    time::Duration,
 };
 use teloxide::{
     dispatching::dialogue::{Serializer, Storage},
@@ -26,8 +30,9 @@ where
     #[error("dialogue serialization error: {0}")]
     SerdeError(SE),
 
+    /// No pooled connection became available within the connection timeout.
     #[error("run error: {0}")]
-    RunError(#[from] RunError<Error>),
+    RunError(RunError<Error>),
 
     #[error("skytable error: {0}")]
     SkytableError(#[from] Error),
@@ -37,8 +42,33 @@ where
     DialogueNotFound,
 }
 
+impl<SE> From<RunError<Error>> for SkytableStorageError<SE>
+where
+    SE: Debug + Display,
+{
+    fn from(err: RunError<Error>) -> Self {
+        match err {
+            RunError::User(err) => SkytableStorageError::SkytableError(err),
+            err => SkytableStorageError::RunError(err),
+        }
+    }
+}
+
+/// Connection pool settings and the retry policy for transient failures.
+#[derive(Debug, Clone)]
+pub struct PoolOptions {
+    pub max_connections: u32,
+    /// How long to wait for a free or a new connection.
+    pub connection_timeout: Duration,
+    /// Number of attempts of every query, including the first one.
+    pub retry_attempts: u32,
+    /// Delay before the second attempt, doubled for each next one.
+    pub retry_initial_backoff: Duration,
+}
+
 pub struct SkytableStorage<S> {
     pool: SkytablePool,
+    options: PoolOptions,
     serializer: S,
 }
 
@@ -48,7 +78,7 @@ impl<S> SkytableStorage<S> {
         skytable_port: u16,
         skytable_user: &str,
         skytable_password: &str,
-        max_connections: u32,
+        options: PoolOptions,
         serializer: S,
     ) -> Result<Arc<Self>, SkytableStorageError<Infallible>> {
         let config = Config::new(
@@ -62,11 +92,57 @@ impl<S> SkytableStorage<S> {
         conn.query_parse::<bool>(&query!(
             "create model if not exists mest_net.dialogues(chat_id: uint64, dialogue: binary)"
         ))?;
-        let pool = pool::get_async(max_connections, config).await.unwrap();
-        Ok(Arc::new(Self { pool, serializer }))
+        // Connections are validated on checkout, so the broken ones are
+        // replaced with new ones instead of being handed out again.
+        let pool = bb8::Pool::builder()
+            .max_size(options.max_connections)
+            .connection_timeout(options.connection_timeout)
+            .test_on_check_out(true)
+            .build(ConnectionMgrTcp::new(config))
+            .await?;
+        Ok(Arc::new(Self {
+            pool,
+            options,
+            serializer,
+        }))
+    }
+
+    /// Takes a connection from the pool, which validates it on checkout.
+    pub async fn ping(&self) -> Result<(), SkytableStorageError<Infallible>> {
+        self.pool.get().await?;
+        Ok(())
+    }
+
+    /// Runs `query` on a pooled connection. I/O failures and connection
+    /// timeouts are retried with exponential backoff, server errors are
+    /// returned right away.
+    async fn run_query<T: FromResponse>(&self, query: &Query) -> Result<T, RunError<Error>> {
+        let mut backoff = self.options.retry_initial_backoff;
+        let mut attempt = 1;
+        loop {
+            let result = match self.pool.get().await {
+                Ok(mut conn) => conn.query_parse::<T>(query).await.map_err(RunError::User),
+                Err(err) => Err(err),
+            };
+            match result {
+                Err(err) if is_transient(&err) && attempt < self.options.retry_attempts => {
+                    log::warn!(
+                        "Skytable query failed (attempt {}/{}), retrying in {:?}: {}",
+                        attempt,
+                        self.options.retry_attempts,
+                        backoff,
+                        err
+                    );
+                    task::sleep(backoff).await;
+                    backoff *= 2;
+                    attempt += 1;
+                }
+                result => return result,
+            }
+        }
     }
 
-    fn log_unexpected_error(chat_id: i64, err: &Error) {
+    fn log_unexpected_error(chat_id: i64, err: &RunError<Error>) {
         log::error!(
             "Unexpected error occurs during fetching dialogue with chat id = {}",
             chat_id
@@ -75,6 +151,10 @@ impl<S> SkytableStorage<S> {
     }
 }
 
+fn is_transient(err: &RunError<Error>) -> bool {
+    matches!(err, RunError::TimedOut | RunError::User(Error::IoError(_)))
+}
+
 impl<S, D> Storage<D> for SkytableStorage<S>
 where
     S: Send + Sync + Serializer<D> + 'static,
@@ -88,10 +168,8 @@ where
         ChatId(chat_id): ChatId,
     ) -> BoxFuture<'static, Result<(), Self::Error>> {
         Box::pin(async move {
-            let mut conn = self.pool.get().await.unwrap();
-
-            let delete_result = conn
-                .query_parse::<()>(&query!(
+            let delete_result = self
+                .run_query::<()>(&query!(
                     "delete from mest_net.dialogues where chat_id = ?",
                     chat_id as u64
                 ))
@@ -102,16 +180,14 @@ where
                     log::info!("Dialogue with chat id = {} successfully deleted", chat_id);
                     Ok(())
                 }
-                Err(skytable_err) => match skytable_err {
-                    Error::ServerError(ROW_NOT_FOUND_CODE) => {
-                        log::info!("Dialogue with chat id = {} not found", chat_id);
-                        Err(SkytableStorageError::DialogueNotFound)
-                    }
-                    err => {
-                        SkytableStorage::<S>::log_unexpected_error(chat_id, &err);
-                        Err(SkytableStorageError::SkytableError(err))
-                    }
-                },
+                Err(RunError::User(Error::ServerError(ROW_NOT_FOUND_CODE))) => {
+                    log::info!("Dialogue with chat id = {} not found", chat_id);
+                    Err(SkytableStorageError::DialogueNotFound)
+                }
+                Err(err) => {
+                    SkytableStorage::<S>::log_unexpected_error(chat_id, &err);
+                    Err(err.into())
+                }
             }
         })
     }
@@ -126,10 +202,9 @@ where
                 .serializer
                 .serialize(&dialogue)
                 .map_err(SkytableStorageError::SerdeError)?;
-            let mut conn = self.pool.get().await.unwrap();
 
-            let insert_result = conn
-                .query_parse::<()>(&query!(
+            let insert_result = self
+                .run_query::<()>(&query!(
                     "insert into mest_net.dialogues(?, ?)",
                     chat_id as u64,
                     &d
@@ -141,35 +216,30 @@ where
                     log::info!("Dialogue with chat id = {} successfully inserted", chat_id);
                     Ok(())
                 }
-                Err(skytable_err) => match skytable_err {
-                    Error::ServerError(ROW_ALREADY_EXISTS_CODE) => {
-                        let update_result = conn
-                            .query_parse::<()>(&query!(
-                                "update mest_net.dialogues set dialogue = ? where chat_id = ?",
-                                &d,
-                                chat_id as u64
-                            ))
-                            .await;
-
-                        match update_result {
-                            Ok(_) => {
-                                log::info!(
-                                    "Dialogue with chat id = {} successfully updated",
-                                    chat_id
-                                );
-                                Ok(())
-                            }
-                            Err(err) => {
-                                SkytableStorage::<S>::log_unexpected_error(chat_id, &err);
-                                Err(SkytableStorageError::SkytableError(skytable_err))
-                            }
+                Err(RunError::User(Error::ServerError(ROW_ALREADY_EXISTS_CODE))) => {
+                    let update_result = self
+                        .run_query::<()>(&query!(
+                            "update mest_net.dialogues set dialogue = ? where chat_id = ?",
+                            &d,
+                            chat_id as u64
+                        ))
+                        .await;
+
+                    match update_result {
+                        Ok(_) => {
+                            log::info!("Dialogue with chat id = {} successfully updated", chat_id);
+                            Ok(())
+                        }
+                        Err(err) => {
+                            SkytableStorage::<S>::log_unexpected_error(chat_id, &err);
+                            Err(err.into())
                         }
                     }
-                    err => {
-                        SkytableStorage::<S>::log_unexpected_error(chat_id, &err);
-                        Err(SkytableStorageError::SkytableError(err))
-                    }
-                },
+                }
+                Err(err) => {
+                    SkytableStorage::<S>::log_unexpected_error(chat_id, &err);
+                    Err(err.into())
+                }
             }
         })
     }
@@ -179,10 +249,8 @@ where
         ChatId(chat_id): ChatId,
     ) -> BoxFuture<'static, Result<Option<D>, Self::Error>> {
         Box::pin(async move {
-            let mut conn = self.pool.get().await.unwrap();
-
-            let dialogue: Option<Vec<u8>> = match conn
-                .query_parse::<(Vec<u8>,)>(&query!(
+            let dialogue: Option<Vec<u8>> = match self
+                .run_query::<(Vec<u8>,)>(&query!(
                     "select dialogue from mest_net.dialogues where chat_id = ?",
                     chat_id as u64
                 ))
@@ -192,16 +260,16 @@ where
                     log::info!("Dialogue with chat id = {} successfully fetched", chat_id);
                     Some(val.0)
                 }
-                Err(skytable_err) => match skytable_err {
-                    Error::ServerError(ROW_NOT_FOUND_CODE) => {
-                        log::info!("Dialogue with chat id = {} not found", chat_id);
-                        None
-                    }
-                    err => {
-                        SkytableStorage::<S>::log_unexpected_error(chat_id, &err);
-                        None
-                    }
-                },
+                Err(RunError::User(Error::ServerError(ROW_NOT_FOUND_CODE))) => {
+                    log::info!("Dialogue with chat id = {} not found", chat_id);
+                    None
+                }
+                // Failing the update is better than silently resetting the
+                // dialogue to the initial state.
+                Err(err) => {
+                    SkytableStorage::<S>::log_unexpected_error(chat_id, &err);
+                    return Err(err.into());
+                }
             };
 
             dialogue
@@ -214,3 +282,45 @@ where
         })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::dialogue_storage::{
+        conformance::check_storage,
+        skytable_storage::{is_transient, PoolOptions, SkytableStorage},
+    };
+    use bb8::RunError;
+    use skytable::error::Error;
+    use std::{env, io, time::Duration};
+    use teloxide::dispatching::dialogue::serializer::Bincode;
+
+    /// Needs `TEST_SKYTABLE_PASSWORD` of the root user of the server on
+    /// `TEST_SKYTABLE_HOST:2003` (localhost by default).
+    #[tokio::test]
+    #[ignore = "needs TEST_SKYTABLE_PASSWORD"]
+    async fn skytable_storage_conforms() {
+        let password =
+            env::var("TEST_SKYTABLE_PASSWORD").expect("TEST_SKYTABLE_PASSWORD is not set");
+        let host = env::var("TEST_SKYTABLE_HOST").unwrap_or_else(|_| "127.0.0.1".to_owned());
+        let options = PoolOptions {
+            max_connections: 4,
+            connection_timeout: Duration::from_secs(5),
+            retry_attempts: 3,
+            retry_initial_backoff: Duration::from_millis(100),
+        };
+        let storage = SkytableStorage::open(&host, 2003, "root", &password, options, Bincode)
+            .await
+            .unwrap();
+
+        check_storage(storage).await;
+    }
+
+    #[test]
+    fn only_connection_failures_are_retried() {
+        assert!(is_transient(&RunError::TimedOut));
+        assert!(is_transient(&RunError::User(Error::IoError(
+            io::ErrorKind::ConnectionReset.into()
+        ))));
+        assert!(!is_transient(&RunError::User(Error::ServerError(111))));
+    }
+}
@@ -0,0 +1,175 @@
+use futures::future::BoxFuture;
+use sea_orm::{
+    sea_query::OnConflict, ActiveValue::Set, ConnectionTrait, DatabaseConnection, DbBackend, DbErr,
+    EntityTrait, Schema,
+};
+use serde::{de::DeserializeOwned, Serialize};
+use std::{
+    convert::Infallible,
+    fmt::{Debug, Display},
+    sync::Arc,
+};
+use teloxide::{
+    dispatching::dialogue::{Serializer, Storage},
+    prelude::ChatId,
+};
+use thiserror::Error;
+
+/// Table with serialized dialogues. The Postgres one comes from the
+/// migrations, the SQLite one is created on [`SqlStorage::open`].
+mod dialogue {
+    use sea_orm::entity::prelude::*;
+
+    #[derive(Clone, Debug, PartialEq, Eq, DeriveEntityModel)]
+    #[sea_orm(table_name = "dialogue")]
+    pub struct Model {
+        #[sea_orm(primary_key, auto_increment = false)]
+        pub chat_id: i64,
+        #[sea_orm(column_type = "Blob")]
+        pub dialogue: Vec<u8>,
+    }
+
+    #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+    pub enum Relation {}
+
+    impl ActiveModelBehavior for ActiveModel {}
+}
+
+#[derive(Debug, Error)]
+pub enum SqlStorageError<SE>
+where
+    SE: Debug + Display,
+{
+    #[error("dialogue serialization error: {0}")]
+    SerdeError(SE),
+
+    #[error("database error: {0}")]
+    DatabaseError(#[from] DbErr),
+
+    /// Returned from [`SqlStorage::remove_dialogue`].
+    #[error("row not found")]
+    DialogueNotFound,
+}
+
+/// Dialogue storage on top of a SeaORM connection, so it works with both
+/// SQLite and the Postgres database of the bot.
+pub struct SqlStorage<S> {
+    db: DatabaseConnection,
+    serializer: S,
+}
+
+impl<S> SqlStorage<S> {
+    pub async fn open(
+        db: DatabaseConnection,
+        serializer: S,
+    ) -> Result<Arc<Self>, SqlStorageError<Infallible>> {
+        let backend = db.get_database_backend();
+        if backend == DbBackend::Sqlite {
+            let create_table = Schema::new(backend)
+                .create_table_from_entity(dialogue::Entity)
+                .if_not_exists()
+                .to_owned();
+            db.execute(backend.build(&create_table)).await?;
+        }
+        Ok(Arc::new(Self { db, serializer }))
+    }
+
+    pub async fn ping(&self) -> Result<(), SqlStorageError<Infallible>> {
+        self.db.ping().await?;
+        Ok(())
+    }
+}
+
+impl<S, D> Storage<D> for SqlStorage<S>
+where
+    S: Send + Sync + Serializer<D> + 'static,
+    D: Send + Serialize + DeserializeOwned + 'static,
+    <S as Serializer<D>>::Error: Debug + Display,
+{
+    type Error = SqlStorageError<<S as Serializer<D>>::Error>;
+
+    fn remove_dialogue(
+        self: Arc<Self>,
+        ChatId(chat_id): ChatId,
+    ) -> BoxFuture<'static, Result<(), Self::Error>> {
+        Box::pin(async move {
+            let delete_result = dialogue::Entity::delete_by_id(chat_id)
+                .exec(&self.db)
+                .await?;
+
+            if delete_result.rows_affected == 0 {
+                return Err(SqlStorageError::DialogueNotFound);
+            }
+            Ok(())
+        })
+    }
+
+    fn update_dialogue(
+        self: Arc<Self>,
+        ChatId(chat_id): ChatId,
+        dialogue: D,
+    ) -> BoxFuture<'static, Result<(), Self::Error>> {
+        Box::pin(async move {
+            let d = self
+                .serializer
+                .serialize(&dialogue)
+                .map_err(SqlStorageError::SerdeError)?;
+
+            dialogue::Entity::insert(dialogue::ActiveModel {
+                chat_id: Set(chat_id),
+                dialogue: Set(d),
+            })
+            .on_conflict(
+                OnConflict::column(dialogue::Column::ChatId)
+                    .update_column(dialogue::Column::Dialogue)
+                    .to_owned(),
+            )
+            .exec_without_returning(&self.db)
+            .await?;
+            Ok(())
+        })
+    }
+
+    fn get_dialogue(
+        self: Arc<Self>,
+        ChatId(chat_id): ChatId,
+    ) -> BoxFuture<'static, Result<Option<D>, Self::Error>> {
+        Box::pin(async move {
+            dialogue::Entity::find_by_id(chat_id)
+                .one(&self.db)
+                .await?
+                .map(|model| {
+                    self.serializer
+                        .deserialize(&model.dialogue)
+                        .map_err(SqlStorageError::SerdeError)
+                })
+                .transpose()
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::dialogue_storage::{conformance::check_storage, sql_storage::SqlStorage};
+    use sea_orm::Database;
+    use std::env;
+    use teloxide::dispatching::dialogue::serializer::Bincode;
+
+    #[tokio::test]
+    async fn sqlite_storage_conforms() {
+        let db = Database::connect("sqlite::memory:").await.unwrap();
+
+        check_storage(SqlStorage::open(db, Bincode).await.unwrap()).await;
+    }
+
+    /// Needs `TEST_DATABASE_URL` pointing to a Postgres database with the
+    /// migrations applied.
+    #[tokio::test]
+    #[ignore = "needs TEST_DATABASE_URL"]
+    async fn postgres_storage_conforms() {
+        let url = env::var("TEST_DATABASE_URL").expect("TEST_DATABASE_URL is not set");
+        let db = Database::connect(url).await.unwrap();
+
+        check_storage(SqlStorage::open(db, Bincode).await.unwrap()).await;
+    }
+}
@@ -10,9 +10,13 @@ pub struct Model {
     #[sea_orm(primary_key)]
     pub id: i32,
     pub tg_id: Option<i64>,
-    pub token: String,
+    /// SHA-256 of the sign-in token, see `manager_tokens`.
+    pub token_hash: String,
     pub share_contact: bool,
     pub restaurant_id: i32,
+    /// Tokens issued before expiry was introduced do not expire.
+    pub token_expires_at: Option<DateTimeWithTimeZone>,
+    pub token_used_at: Option<DateTimeWithTimeZone>,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
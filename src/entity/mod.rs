@@ -2,5 +2,9 @@
 
 pub mod prelude;
 
+pub mod booking_hold;
+pub mod booking_notification;
+pub mod booking_request;
+pub mod booking_request_answer;
 pub mod manager;
 pub mod restaurant;
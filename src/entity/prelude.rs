@@ -1,3 +1,8 @@
 //! `SeaORM` Entity, @generated by sea-orm-codegen 1.0.0-rc.5
 
-pub use super::{manager::Entity as Manager, restaurant::Entity as Restaurant};
+pub use super::{
+    booking_hold::Entity as BookingHold, booking_notification::Entity as BookingNotification,
+    booking_request::Entity as BookingRequest,
+    booking_request_answer::Entity as BookingRequestAnswer, manager::Entity as Manager,
+    restaurant::Entity as Restaurant,
+};
@@ -3,13 +3,20 @@
 use std::fmt::{Display, Formatter};
 
 use chrono::{
-    DateTime, Datelike, Local, NaiveTime,
+    DateTime, Datelike, NaiveDate, NaiveTime, TimeZone, Utc, Weekday,
     Weekday::{Fri, Sat, Sun},
 };
-use sea_orm::{entity::prelude::*, FromJsonQueryResult, FromQueryResult};
+use chrono_tz::Tz;
+use sea_orm::{
+    entity::prelude::*, ColIdx, FromJsonQueryResult, FromQueryResult, QueryResult, TryGetError,
+    TryGetable,
+};
 use serde::{Deserialize, Serialize};
 
-use crate::utils::constants::{DAY_END, MIDNIGHT};
+use crate::{
+    config::ScoringConfig,
+    utils::constants::{DAY_END, DEFAULT_TIME_ZONE, MIDNIGHT},
+};
 
 #[derive(Clone, Debug, DeriveEntityModel)]
 #[sea_orm(table_name = "restaurant")]
@@ -24,6 +31,17 @@ pub struct Model {
     pub schedule: Schedule,
     pub score: i32,
     pub phone_number: String,
+    /// Stable key of the restaurant in imported files.
+    #[sea_orm(unique)]
+    pub external_id: String,
+    /// IANA name of the time zone the schedule is in.
+    pub time_zone: String,
+}
+
+impl Model {
+    pub fn time_zone(&self) -> Tz {
+        parse_time_zone(&self.time_zone)
+    }
 }
 
 #[derive(FromQueryResult)]
@@ -39,20 +57,92 @@ pub struct RestaurantWithManagerInfo {
     pub phone_number: String,
     pub manager_tg_id: i64,
     pub share_manager_contact: bool,
+    pub time_zone: RestaurantTimeZone,
+    /// Distance in meters from the searching user, if it was requested.
+    pub distance: Option<f64>,
 }
 
 impl RestaurantWithManagerInfo {
     pub fn is_open(&self) -> bool {
-        self.schedule.match_in(Local::now())
+        self.is_open_at(Utc::now())
+    }
+
+    /// Evaluates the schedule at the local time of the restaurant.
+    pub fn is_open_at<Z: TimeZone>(&self, date_time: DateTime<Z>) -> bool {
+        self.schedule
+            .match_in(date_time.with_timezone(&self.time_zone()))
+    }
+
+    pub fn time_zone(&self) -> Tz {
+        self.time_zone.0
+    }
+
+    /// Blends proximity within `search_radius` and score into a rank from 0
+    /// to 1, the higher the better.
+    pub fn rank(&self, search_radius: u16, scoring_config: &ScoringConfig) -> f64 {
+        let proximity = self.distance.map_or(0.0, |distance| {
+            1.0 - (distance / search_radius as f64).min(1.0)
+        });
+        let score = self.score as f64 / scoring_config.max_restaurant_score as f64;
+        let distance_rank_weight = scoring_config.distance_rank_weight;
+        distance_rank_weight * proximity + (1.0 - distance_rank_weight) * score
+    }
+}
+
+/// Time zones are validated on import, so an unknown one means the restaurant
+/// was edited by hand. The registry reports such restaurants on every sync,
+/// and the default time zone is used for them.
+fn parse_time_zone(name: &str) -> Tz {
+    name.parse().unwrap_or(DEFAULT_TIME_ZONE)
+}
+
+/// Time zone of a restaurant, parsed once when the restaurant is loaded, since
+/// the schedule is evaluated in it on every search.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RestaurantTimeZone(pub Tz);
+
+impl RestaurantTimeZone {
+    pub fn from_name(name: &str) -> Self {
+        Self(parse_time_zone(name))
+    }
+}
+
+impl TryGetable for RestaurantTimeZone {
+    fn try_get_by<I: ColIdx>(res: &QueryResult, index: I) -> Result<Self, TryGetError> {
+        String::try_get_by(res, index).map(|name| Self::from_name(&name))
     }
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
 pub enum Relation {
+    #[sea_orm(has_many = "super::booking_hold::Entity")]
+    BookingHold,
+    #[sea_orm(has_many = "super::booking_notification::Entity")]
+    BookingNotification,
+    #[sea_orm(has_many = "super::booking_request_answer::Entity")]
+    BookingRequestAnswer,
     #[sea_orm(has_many = "super::manager::Entity")]
     Manager,
 }
 
+impl Related<super::booking_hold::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::BookingHold.def()
+    }
+}
+
+impl Related<super::booking_notification::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::BookingNotification.def()
+    }
+}
+
+impl Related<super::booking_request_answer::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::BookingRequestAnswer.def()
+    }
+}
+
 impl Related<super::manager::Entity> for Entity {
     fn to() -> RelationDef {
         Relation::Manager.def()
@@ -81,12 +171,21 @@ pub enum Schedule {
         weekday_working_time: WorkingTime,
         weekend_working_time: WorkingTime,
     },
+    /// Working times for each day of the week, with breaks as gaps between
+    /// them, and dates on which the restaurant works differently.
+    Weekly {
+        days: WeeklyWorkingTimes,
+        #[serde(default)]
+        exceptions: Vec<ScheduleException>,
+    },
 }
 
 impl Schedule {
-    pub fn match_in(&self, passed_date_time: DateTime<Local>) -> bool {
-        fn match_in(
-            passed_date_time: DateTime<Local>,
+    /// Whether the restaurant is open at the passed time, which should be in
+    /// its time zone.
+    pub fn match_in<Z: TimeZone>(&self, passed_date_time: DateTime<Z>) -> bool {
+        fn match_in<Z: TimeZone>(
+            passed_date_time: &DateTime<Z>,
             start_time: &NaiveTime,
             end_time: &NaiveTime,
         ) -> bool {
@@ -100,7 +199,7 @@ impl Schedule {
         }
         match &self {
             Schedule::Regular { working_time } => match_in(
-                passed_date_time,
+                &passed_date_time,
                 &working_time.start_time,
                 &working_time.end_time,
             ),
@@ -114,20 +213,20 @@ impl Schedule {
                         let time = passed_date_time.time();
                         if time < weekday_working_time.start_time {
                             match_in(
-                                passed_date_time,
+                                &passed_date_time,
                                 &weekday_working_time.start_time,
                                 &weekday_working_time.end_time,
                             )
                         } else {
                             match_in(
-                                passed_date_time,
+                                &passed_date_time,
                                 &weekend_working_time.start_time,
                                 &weekend_working_time.end_time,
                             )
                         }
                     }
                     Sat => match_in(
-                        passed_date_time,
+                        &passed_date_time,
                         &weekend_working_time.start_time,
                         &weekend_working_time.end_time,
                     ),
@@ -135,25 +234,46 @@ impl Schedule {
                         let time = passed_date_time.time();
                         if time < weekend_working_time.start_time {
                             match_in(
-                                passed_date_time,
+                                &passed_date_time,
                                 &weekend_working_time.start_time,
                                 &weekend_working_time.end_time,
                             )
                         } else {
                             match_in(
-                                passed_date_time,
+                                &passed_date_time,
                                 &weekday_working_time.start_time,
                                 &weekday_working_time.end_time,
                             )
                         }
                     }
                     _ => match_in(
-                        passed_date_time,
+                        &passed_date_time,
                         &weekday_working_time.start_time,
                         &weekday_working_time.end_time,
                     ),
                 }
             }
+            Schedule::Weekly { days, exceptions } => {
+                let working_times_on = |date: NaiveDate| {
+                    exceptions
+                        .iter()
+                        .find(|exception| exception.date == date)
+                        .map_or_else(
+                            || days.on(date.weekday()),
+                            |exception| exception.working_times.as_slice(),
+                        )
+                };
+                let date = passed_date_time.date_naive();
+                let time = passed_date_time.time();
+                working_times_on(date)
+                    .iter()
+                    .any(|working_time| working_time.contains_on_start_day(time))
+                    || date.pred_opt().is_some_and(|previous_date| {
+                        working_times_on(previous_date)
+                            .iter()
+                            .any(|working_time| working_time.contains_on_next_day(time))
+                    })
+            }
         }
     }
 }
@@ -164,15 +284,151 @@ pub struct WorkingTime {
     pub end_time: NaiveTime,
 }
 
+impl WorkingTime {
+    /// Whether the working time goes past midnight into the next day.
+    pub fn is_overnight(&self) -> bool {
+        self.start_time > self.end_time
+    }
+
+    fn contains_on_start_day(&self, time: NaiveTime) -> bool {
+        time >= self.start_time && (self.is_overnight() || time <= self.end_time)
+    }
+
+    fn contains_on_next_day(&self, time: NaiveTime) -> bool {
+        self.is_overnight() && time <= self.end_time
+    }
+}
+
+/// Working times of [`Schedule::Weekly`]. A day without working times is a day
+/// off, and a working time past midnight belongs to the day it starts on.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct WeeklyWorkingTimes {
+    pub monday: Vec<WorkingTime>,
+    pub tuesday: Vec<WorkingTime>,
+    pub wednesday: Vec<WorkingTime>,
+    pub thursday: Vec<WorkingTime>,
+    pub friday: Vec<WorkingTime>,
+    pub saturday: Vec<WorkingTime>,
+    pub sunday: Vec<WorkingTime>,
+}
+
+impl WeeklyWorkingTimes {
+    pub fn on(&self, weekday: Weekday) -> &[WorkingTime] {
+        match weekday {
+            Weekday::Mon => &self.monday,
+            Weekday::Tue => &self.tuesday,
+            Weekday::Wed => &self.wednesday,
+            Weekday::Thu => &self.thursday,
+            Weekday::Fri => &self.friday,
+            Weekday::Sat => &self.saturday,
+            Weekday::Sun => &self.sunday,
+        }
+    }
+}
+
+/// Working times replacing the weekly ones on a holiday or another special
+/// date, no working times mean the restaurant is closed.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ScheduleException {
+    pub date: NaiveDate,
+    #[serde(default)]
+    pub working_times: Vec<WorkingTime>,
+}
+
 #[cfg(test)]
 mod tests {
 
+    mod rank_tests {
+        use crate::{
+            config::ScoringConfig,
+            entity::restaurant::{
+                RestaurantTimeZone, RestaurantWithManagerInfo, Schedule, WorkingTime,
+            },
+        };
+        use chrono::NaiveTime;
+
+        fn restaurant(score: i32, distance: Option<f64>) -> RestaurantWithManagerInfo {
+            let working_time = WorkingTime {
+                start_time: NaiveTime::from_hms_milli_opt(8, 0, 0, 0).unwrap(),
+                end_time: NaiveTime::from_hms_milli_opt(23, 0, 0, 0).unwrap(),
+            };
+            RestaurantWithManagerInfo {
+                id: 1,
+                name: "Test".to_owned(),
+                maps_url: String::new(),
+                average_price: String::new(),
+                segment: String::new(),
+                kitchen: String::new(),
+                schedule: Schedule::Regular { working_time },
+                score,
+                phone_number: String::new(),
+                manager_tg_id: 1,
+                share_manager_contact: false,
+                time_zone: RestaurantTimeZone::from_name("Europe/Moscow"),
+                distance,
+            }
+        }
+
+        #[test]
+        fn closer_restaurant_with_same_score_ranks_higher() {
+            let close = restaurant(50, Some(100.0));
+            let far = restaurant(50, Some(900.0));
+
+            let scoring_config = ScoringConfig::default();
+
+            assert!(close.rank(1000, &scoring_config) > far.rank(1000, &scoring_config))
+        }
+
+        #[test]
+        fn higher_score_outweighs_small_distance_difference() {
+            let close = restaurant(10, Some(300.0));
+            let far = restaurant(150, Some(400.0));
+
+            let scoring_config = ScoringConfig::default();
+
+            assert!(far.rank(1000, &scoring_config) > close.rank(1000, &scoring_config))
+        }
+    }
+
     mod schedule_tests {
         use crate::entity::restaurant::{
-            Schedule::{Regular, WithWeekends},
-            WorkingTime,
+            RestaurantTimeZone, RestaurantWithManagerInfo,
+            Schedule::{self, Regular, Weekly, WithWeekends},
+            ScheduleException, WeeklyWorkingTimes, WorkingTime,
         };
-        use chrono::{DateTime, Local, NaiveTime, TimeZone};
+        use chrono::{DateTime, Local, NaiveDate, NaiveTime, TimeZone, Utc};
+        use serde_json::json;
+
+        fn working_time(start_hour: u32, end_hour: u32) -> WorkingTime {
+            WorkingTime {
+                start_time: NaiveTime::from_hms_milli_opt(start_hour, 0, 0, 0).unwrap(),
+                end_time: NaiveTime::from_hms_milli_opt(end_hour, 0, 0, 0).unwrap(),
+            }
+        }
+
+        /// Kitchen break on Mondays, open past midnight on Fridays, closed on
+        /// Saturdays and on New Year, and open in the evening on 2024-01-10
+        /// only among Wednesdays.
+        fn weekly_schedule() -> Schedule {
+            Weekly {
+                days: WeeklyWorkingTimes {
+                    monday: vec![working_time(12, 15), working_time(16, 23)],
+                    friday: vec![working_time(12, 2)],
+                    ..WeeklyWorkingTimes::default()
+                },
+                exceptions: vec![
+                    ScheduleException {
+                        date: NaiveDate::from_ymd_opt(2024, 1, 1).unwrap(),
+                        working_times: Vec::new(),
+                    },
+                    ScheduleException {
+                        date: NaiveDate::from_ymd_opt(2024, 1, 10).unwrap(),
+                        working_times: vec![working_time(18, 22)],
+                    },
+                ],
+            }
+        }
 
         #[test]
         fn regular_schedule_one_day_match_in() {
@@ -507,5 +763,117 @@ mod tests {
 
             assert!(schedule.match_in(current_date_time))
         }
+
+        #[test]
+        fn schedule_is_evaluated_in_restaurant_time_zone() {
+            let restaurant = |time_zone: &str| RestaurantWithManagerInfo {
+                id: 1,
+                name: "Test".to_owned(),
+                maps_url: String::new(),
+                average_price: String::new(),
+                segment: String::new(),
+                kitchen: String::new(),
+                schedule: Regular {
+                    working_time: working_time(8, 23),
+                },
+                score: 100,
+                phone_number: String::new(),
+                manager_tg_id: 1,
+                share_manager_contact: false,
+                time_zone: RestaurantTimeZone::from_name(time_zone),
+                distance: None,
+            };
+            // 22:00 in Moscow and 05:00 next day in Vladivostok
+            let date_time = Utc.with_ymd_and_hms(2024, 1, 8, 19, 0, 0).unwrap();
+
+            assert!(restaurant("Europe/Moscow").is_open_at(date_time));
+            assert!(!restaurant("Asia/Vladivostok").is_open_at(date_time));
+            assert!(restaurant("Saint Petersburg").is_open_at(date_time));
+        }
+
+        #[test]
+        fn weekly_schedule_around_break_match_in() {
+            let schedule = weekly_schedule();
+
+            assert!(schedule.match_in(Local.with_ymd_and_hms(2024, 1, 8, 13, 0, 0).unwrap()));
+            assert!(!schedule.match_in(Local.with_ymd_and_hms(2024, 1, 8, 15, 30, 0).unwrap()));
+            assert!(schedule.match_in(Local.with_ymd_and_hms(2024, 1, 8, 17, 0, 0).unwrap()));
+        }
+
+        #[test]
+        fn weekly_schedule_overnight_match_in_next_day() {
+            let schedule = weekly_schedule();
+
+            assert!(schedule.match_in(Local.with_ymd_and_hms(2024, 1, 12, 23, 30, 0).unwrap()));
+            assert!(schedule.match_in(Local.with_ymd_and_hms(2024, 1, 13, 1, 30, 0).unwrap()));
+            assert!(!schedule.match_in(Local.with_ymd_and_hms(2024, 1, 13, 2, 30, 0).unwrap()));
+        }
+
+        #[test]
+        fn weekly_schedule_day_off_not_match_in() {
+            let schedule = weekly_schedule();
+
+            assert!(!schedule.match_in(Local.with_ymd_and_hms(2024, 1, 13, 13, 0, 0).unwrap()));
+            assert!(!schedule.match_in(Local.with_ymd_and_hms(2024, 1, 3, 19, 0, 0).unwrap()));
+        }
+
+        #[test]
+        fn weekly_schedule_exceptions_match_in() {
+            let schedule = weekly_schedule();
+
+            assert!(!schedule.match_in(Local.with_ymd_and_hms(2024, 1, 1, 13, 0, 0).unwrap()));
+            assert!(schedule.match_in(Local.with_ymd_and_hms(2024, 1, 10, 19, 0, 0).unwrap()));
+            assert!(!schedule.match_in(Local.with_ymd_and_hms(2024, 1, 10, 13, 0, 0).unwrap()));
+        }
+
+        #[test]
+        fn schedules_keep_tagged_json_format() {
+            let with_weekends = json!({
+                "type": "WithWeekends",
+                "content": {
+                    "weekday_working_time": {"start_time": "08:00:00", "end_time": "01:00:00"},
+                    "weekend_working_time": {"start_time": "12:00:00", "end_time": "06:00:00"}
+                }
+            });
+            let weekly = json!({
+                "type": "Weekly",
+                "content": {
+                    "days": {
+                        "monday": [
+                            {"start_time": "12:00:00", "end_time": "15:00:00"},
+                            {"start_time": "16:00:00", "end_time": "23:00:00"}
+                        ]
+                    }
+                }
+            });
+
+            assert_eq!(
+                serde_json::from_value::<Schedule>(with_weekends).unwrap(),
+                WithWeekends {
+                    weekday_working_time: working_time(8, 1),
+                    weekend_working_time: working_time(12, 6),
+                }
+            );
+            assert_eq!(
+                serde_json::from_value::<Schedule>(weekly).unwrap(),
+                Weekly {
+                    days: WeeklyWorkingTimes {
+                        monday: vec![working_time(12, 15), working_time(16, 23)],
+                        ..WeeklyWorkingTimes::default()
+                    },
+                    exceptions: Vec::new(),
+                }
+            );
+            let json = serde_json::to_value(weekly_schedule()).unwrap();
+            assert_eq!(json["type"], json!("Weekly"));
+            assert_eq!(
+                json["content"]["exceptions"][0],
+                json!({"date": "2024-01-01", "working_times": []})
+            );
+            assert_eq!(
+                serde_json::from_value::<Schedule>(json).unwrap(),
+                weekly_schedule()
+            );
+        }
     }
 }
@@ -0,0 +1,18 @@
+//! Mest Net bot, run by the `mest-net` binary, and the `mest-net-admin` tool.
+
+pub mod admin;
+mod background_processing;
+mod bot;
+mod config;
+mod db;
+mod dialogue_storage;
+mod entity;
+mod manager_tokens;
+mod model;
+mod monitoring;
+mod schema;
+#[cfg(test)]
+mod testing;
+mod utils;
+
+pub use bot::run;
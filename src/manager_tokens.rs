@@ -0,0 +1,201 @@
+//! Tokens restaurant managers sign in with. A token is random, expires and
+//! can be redeemed once; only its SHA-256 hash is stored.
+use crate::{
+    db::{DatabaseHandler, DbResult},
+    entity::manager,
+    monitoring::audit::{self, AuditEvent, TokenActor},
+};
+use chrono::{DateTime, Duration, Local};
+use rand::{distributions::Alphanumeric, Rng};
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use thiserror::Error;
+
+const TOKEN_LENGTH: usize = 32;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum ManagerTokenAction {
+    /// Adds one more manager token to the restaurant.
+    Issue,
+    /// Revokes the tokens of the restaurant and issues a new one.
+    Rotate,
+    /// Revokes the tokens of the restaurant, signing its managers out.
+    Revoke,
+}
+
+/// Result of [`apply`].
+pub(crate) enum TokenActionOutcome {
+    /// Token to hand over to the manager.
+    Issued(IssuedToken),
+    Revoked,
+    /// A restaurant has one signed-in manager at most, so no token is issued
+    /// while it has one. Rotating the tokens replaces the manager.
+    ManagerSignedIn,
+}
+
+/// Token to hand over to a manager, it is not stored anywhere.
+pub(crate) struct IssuedToken {
+    pub token: String,
+    pub expires_at: DateTime<Local>,
+}
+
+/// Why a manager could not sign in with a token.
+#[derive(Debug, Clone, Copy, PartialEq, Error, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum TokenRejection {
+    #[error("unknown token")]
+    Unknown,
+
+    #[error("token is already used")]
+    Used,
+
+    #[error("token has expired")]
+    Expired,
+
+    #[error("restaurant already has a signed-in manager")]
+    ManagerSignedIn,
+}
+
+pub(crate) fn hash_token(token: &str) -> String {
+    format!("{:x}", Sha256::digest(token.trim().as_bytes()))
+}
+
+fn generate_token() -> String {
+    rand::thread_rng()
+        .sample_iter(&Alphanumeric)
+        .take(TOKEN_LENGTH)
+        .map(char::from)
+        .collect()
+}
+
+/// Applies `action` to the manager tokens of the restaurant and records it in
+/// the audit log.
+pub(crate) async fn apply(
+    db_handler: &DatabaseHandler,
+    restaurant_id: i32,
+    action: ManagerTokenAction,
+    ttl_hours: u32,
+    actor: TokenActor,
+) -> DbResult<TokenActionOutcome> {
+    if action == ManagerTokenAction::Revoke {
+        let manager_ids = db_handler.revoke_manager_tokens(restaurant_id).await?;
+        audit::record(AuditEvent::ManagerTokensRevoked {
+            restaurant_id,
+            manager_ids: &manager_ids,
+            actor,
+        });
+        return Ok(TokenActionOutcome::Revoked);
+    }
+    if action == ManagerTokenAction::Issue
+        && db_handler.has_signed_in_manager(restaurant_id).await?
+    {
+        return Ok(TokenActionOutcome::ManagerSignedIn);
+    }
+
+    let token = generate_token();
+    let expires_at = Local::now() + Duration::hours(ttl_hours.into());
+    let manager_id = if action == ManagerTokenAction::Rotate {
+        let (manager_id, revoked_ids) = db_handler
+            .rotate_manager_tokens(restaurant_id, hash_token(&token), expires_at)
+            .await?;
+        audit::record(AuditEvent::ManagerTokensRevoked {
+            restaurant_id,
+            manager_ids: &revoked_ids,
+            actor,
+        });
+        manager_id
+    } else {
+        db_handler
+            .issue_manager_token(restaurant_id, hash_token(&token), expires_at)
+            .await?
+    };
+    audit::record(AuditEvent::ManagerTokenIssued {
+        restaurant_id,
+        manager_id,
+        expires_at,
+        actor,
+    });
+    Ok(TokenActionOutcome::Issued(IssuedToken {
+        token,
+        expires_at,
+    }))
+}
+
+/// Checks that a manager may still sign in with the token it was found by.
+pub(crate) fn check_redeemable(
+    manager: &manager::Model,
+    now: DateTime<Local>,
+) -> Result<(), TokenRejection> {
+    if manager.tg_id.is_some() || manager.token_used_at.is_some() {
+        return Err(TokenRejection::Used);
+    }
+    match manager.token_expires_at {
+        Some(expires_at) if expires_at <= now => Err(TokenRejection::Expired),
+        _ => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        entity::manager,
+        manager_tokens::{check_redeemable, generate_token, hash_token, TokenRejection},
+    };
+    use chrono::{Duration, Local};
+
+    fn manager() -> manager::Model {
+        manager::Model {
+            id: 1,
+            tg_id: None,
+            token_hash: hash_token("token"),
+            share_contact: false,
+            restaurant_id: 1,
+            token_expires_at: None,
+            token_used_at: None,
+        }
+    }
+
+    #[test]
+    fn tokens_are_random_and_hashed_irrespective_of_surrounding_spaces() {
+        let token = generate_token();
+
+        assert_eq!(token.len(), 32);
+        assert_ne!(token, generate_token());
+        assert_eq!(hash_token(&format!(" {token}\n")), hash_token(&token));
+        assert_eq!(
+            hash_token("Manager 1"),
+            "b1686a564fe55878e2a44c26f465815823d63172dd0fd667e900e506215fd076"
+        );
+    }
+
+    #[test]
+    fn fresh_token_is_redeemable() {
+        let now = Local::now();
+        let manager = manager::Model {
+            token_expires_at: Some((now + Duration::hours(1)).fixed_offset()),
+            ..manager()
+        };
+
+        assert_eq!(check_redeemable(&manager, now), Ok(()));
+        assert_eq!(check_redeemable(&self::manager(), now), Ok(()));
+    }
+
+    #[test]
+    fn used_or_expired_token_is_rejected() {
+        let now = Local::now();
+        let used = manager::Model {
+            token_used_at: Some(now.fixed_offset()),
+            ..manager()
+        };
+        let expired = manager::Model {
+            token_expires_at: Some(now.fixed_offset()),
+            ..manager()
+        };
+
+        assert_eq!(check_redeemable(&used, now), Err(TokenRejection::Used));
+        assert_eq!(
+            check_redeemable(&expired, now),
+            Err(TokenRejection::Expired)
+        );
+    }
+}
@@ -0,0 +1,22 @@
+#[derive(Clone, Debug)]
+#[allow(clippy::enum_variant_names)]
+pub(crate) enum BookingEvent {
+    /// All restaurants for the booking request were notified or answered from
+    /// an active hold.
+    RequestDispatched {
+        booking_request_id: i32,
+    },
+    /// Notifying the restaurants failed with a database error of the given
+    /// [`kind`](crate::db::DatabaseError::kind).
+    RequestFailed {
+        booking_request_id: i32,
+        error_kind: &'static str,
+    },
+    RequestCancelled {
+        booking_request_id: i32,
+    },
+    RequestAnswered {
+        booking_request_id: i32,
+        restaurant_id: i32,
+    },
+}
@@ -1,48 +1,81 @@
 use chrono::{DateTime, Local};
-
-use crate::utils::constants::MAX_SUPPORTED_PERSONS;
+use std::collections::HashMap;
 
 #[derive(Debug)]
 pub(crate) struct BookingInfo {
-    pub booking_state: u8,
-    pub notifications_state: u8,
-    pub booking_request_expiration_times: [DateTime<Local>; MAX_SUPPORTED_PERSONS as usize],
-    pub booking_expiration_times: [DateTime<Local>; MAX_SUPPORTED_PERSONS as usize],
+    /// Expiration times of pending manager notifications keyed by party size.
+    pub booking_request_expiration_times: HashMap<u8, DateTime<Local>>,
+    /// Expiration times of approved holds keyed by party size.
+    pub booking_expiration_times: HashMap<u8, DateTime<Local>>,
     pub restaurant_name: String,
 }
 
 impl BookingInfo {
     pub(crate) fn new(restaurant_name: String) -> Self {
         BookingInfo {
-            booking_state: 0,
-            notifications_state: 0,
-            booking_request_expiration_times: [DateTime::default(); MAX_SUPPORTED_PERSONS as usize],
-            booking_expiration_times: [DateTime::default(); MAX_SUPPORTED_PERSONS as usize],
+            booking_request_expiration_times: HashMap::new(),
+            booking_expiration_times: HashMap::new(),
             restaurant_name,
         }
     }
 
-    pub(crate) fn get_booking_request_expiration_time(&self, index: usize) -> &DateTime<Local> {
-        &self.booking_request_expiration_times[index]
+    pub(crate) fn get_booking_request_expiration_time(
+        &self,
+        person_number: u8,
+    ) -> Option<&DateTime<Local>> {
+        self.booking_request_expiration_times.get(&person_number)
     }
 
     pub(crate) fn set_booking_request_expiration_time(
         &mut self,
-        index: usize,
+        person_number: u8,
         time_to_set: DateTime<Local>,
     ) {
-        self.booking_request_expiration_times[index] = time_to_set
+        self.booking_request_expiration_times
+            .insert(person_number, time_to_set);
+    }
+
+    pub(crate) fn remove_booking_request(&mut self, person_number: u8) -> bool {
+        self.booking_request_expiration_times
+            .remove(&person_number)
+            .is_some()
     }
 
-    pub(crate) fn get_booking_expiration_time(&self, index: usize) -> &DateTime<Local> {
-        &self.booking_expiration_times[index]
+    /// Removes pending notifications that expired before `current_time` and
+    /// returns their party sizes.
+    pub(crate) fn remove_expired_booking_requests(
+        &mut self,
+        current_time: &DateTime<Local>,
+    ) -> Vec<u8> {
+        let expired_person_numbers = self
+            .booking_request_expiration_times
+            .iter()
+            .filter(|(_, expiration_time)| current_time > *expiration_time)
+            .map(|(person_number, _)| *person_number)
+            .collect::<Vec<u8>>();
+        for person_number in &expired_person_numbers {
+            self.booking_request_expiration_times.remove(person_number);
+        }
+        expired_person_numbers
+    }
+
+    pub(crate) fn get_booking_expiration_time(
+        &self,
+        person_number: u8,
+    ) -> Option<&DateTime<Local>> {
+        self.booking_expiration_times.get(&person_number)
     }
 
     pub(crate) fn set_booking_expiration_time(
         &mut self,
-        index: usize,
+        person_number: u8,
         time_to_set: DateTime<Local>,
     ) {
-        self.booking_expiration_times[index] = time_to_set
+        self.booking_expiration_times
+            .insert(person_number, time_to_set);
+    }
+
+    pub(crate) fn remove_booking(&mut self, person_number: u8) {
+        self.booking_expiration_times.remove(&person_number);
     }
 }
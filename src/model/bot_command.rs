@@ -7,6 +7,13 @@ pub(crate) enum BotCommand {
     Start,
     #[command(description = "Сбросить состояние диалога")]
     Reset,
+    #[command(
+        rename = "sign_out",
+        description = "Выйти из администраторов ресторана"
+    )]
+    SignOut,
+    #[command(description = "Отменить поиск мест")]
+    Cancel,
     #[command(description = "Показать список всех команд")]
     Help,
     #[command(description = "Обратная связь")]
@@ -0,0 +1,128 @@
+use std::{
+    fmt::{Display, Formatter},
+    str::FromStr,
+};
+use thiserror::Error;
+
+const BOOKING_ANSWER_PREFIX: &str = "booking_answer";
+const RESTAURANT_SELECTION_PREFIX: &str = "restaurant_selection";
+const CANCEL_SEARCH_PREFIX: &str = "cancel_search";
+
+/// Payload of inline keyboard buttons. Telegram limits it to 64 bytes, so it
+/// is encoded as a short colon separated string.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) enum CallbackData {
+    BookingAnswer {
+        booking_request_id: i32,
+        approved: bool,
+    },
+    RestaurantSelection {
+        booking_request_id: i32,
+        restaurant_id: i32,
+    },
+    CancelSearch {
+        booking_request_id: i32,
+    },
+}
+
+#[derive(Debug, Error, PartialEq)]
+#[error("invalid callback data: {0}")]
+pub(crate) struct ParseCallbackDataError(String);
+
+impl Display for CallbackData {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CallbackData::BookingAnswer {
+                booking_request_id,
+                approved,
+            } => write!(
+                f,
+                "{}:{}:{}",
+                BOOKING_ANSWER_PREFIX, booking_request_id, *approved as u8
+            ),
+            CallbackData::RestaurantSelection {
+                booking_request_id,
+                restaurant_id,
+            } => write!(
+                f,
+                "{}:{}:{}",
+                RESTAURANT_SELECTION_PREFIX, booking_request_id, restaurant_id
+            ),
+            CallbackData::CancelSearch { booking_request_id } => {
+                write!(f, "{}:{}", CANCEL_SEARCH_PREFIX, booking_request_id)
+            }
+        }
+    }
+}
+
+impl FromStr for CallbackData {
+    type Err = ParseCallbackDataError;
+
+    fn from_str(data: &str) -> Result<Self, Self::Err> {
+        let err = || ParseCallbackDataError(data.to_owned());
+        let tokens = data.split(':').collect::<Vec<&str>>();
+        match tokens.as_slice() {
+            [BOOKING_ANSWER_PREFIX, booking_request_id, approved] => {
+                Ok(CallbackData::BookingAnswer {
+                    booking_request_id: booking_request_id.parse().map_err(|_| err())?,
+                    approved: match *approved {
+                        "1" => true,
+                        "0" => false,
+                        _ => return Err(err()),
+                    },
+                })
+            }
+            [RESTAURANT_SELECTION_PREFIX, booking_request_id, restaurant_id] => {
+                Ok(CallbackData::RestaurantSelection {
+                    booking_request_id: booking_request_id.parse().map_err(|_| err())?,
+                    restaurant_id: restaurant_id.parse().map_err(|_| err())?,
+                })
+            }
+            [CANCEL_SEARCH_PREFIX, booking_request_id] => Ok(CallbackData::CancelSearch {
+                booking_request_id: booking_request_id.parse().map_err(|_| err())?,
+            }),
+            _ => Err(err()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::model::callback_data::CallbackData;
+
+    #[test]
+    fn booking_answer_round_trip() {
+        let callback_data = CallbackData::BookingAnswer {
+            booking_request_id: 42,
+            approved: true,
+        };
+
+        assert_eq!(
+            callback_data.to_string().parse::<CallbackData>(),
+            Ok(callback_data)
+        )
+    }
+
+    #[test]
+    fn booking_answer_invalid_flag() {
+        assert!("booking_answer:42:yes".parse::<CallbackData>().is_err())
+    }
+
+    #[test]
+    fn restaurant_selection_round_trip() {
+        let callback_data = CallbackData::RestaurantSelection {
+            booking_request_id: 42,
+            restaurant_id: 7,
+        };
+
+        assert_eq!(
+            callback_data.to_string().parse::<CallbackData>(),
+            Ok(callback_data)
+        )
+    }
+
+    #[test]
+    fn unknown_prefix() {
+        assert!("unknown:42:1".parse::<CallbackData>().is_err())
+    }
+}
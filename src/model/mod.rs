@@ -1,5 +1,10 @@
+pub(crate) mod booking_event;
 pub(crate) mod booking_info;
 pub(crate) mod bot_command;
+pub(crate) mod callback_data;
 pub(crate) mod mest_check_command;
+pub(crate) mod restaurant_record;
+pub(crate) mod search_filters;
 pub(crate) mod state;
+pub(crate) mod super_admin_command;
 pub(crate) mod types;
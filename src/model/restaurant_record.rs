@@ -0,0 +1,262 @@
+use crate::{
+    entity::restaurant::{Schedule, WorkingTime},
+    utils::constants::DEFAULT_TIME_ZONE,
+};
+use chrono::{NaiveDate, Weekday};
+use chrono_tz::Tz;
+use sea_orm::FromQueryResult;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+/// Restaurant as imported from and exported to files by `mest-net-admin`.
+/// Restaurants are matched by `external_id`, scores are not part of it.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize, FromQueryResult)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct RestaurantRecord {
+    pub external_id: String,
+    pub name: String,
+    pub maps_url: String,
+    pub average_price: String,
+    pub segment: String,
+    pub kitchen: String,
+    pub phone_number: String,
+    pub longitude: f64,
+    pub latitude: f64,
+    /// IANA name of the time zone the schedule is in.
+    #[serde(default = "default_time_zone")]
+    pub time_zone: String,
+    pub schedule: Schedule,
+}
+
+pub(crate) fn default_time_zone() -> String {
+    DEFAULT_TIME_ZONE.name().to_owned()
+}
+
+#[derive(Debug, Error, PartialEq)]
+pub(crate) enum InvalidRestaurantRecord {
+    #[error("{0} is empty")]
+    EmptyField(&'static str),
+
+    #[error("longitude {0} is not between -180 and 180")]
+    Longitude(f64),
+
+    #[error("latitude {0} is not between -90 and 90")]
+    Latitude(f64),
+
+    #[error("unknown time zone {0}")]
+    TimeZone(String),
+
+    #[error("working time starts and ends at {0}")]
+    EmptyWorkingTime(String),
+
+    #[error("schedule has more than one exception on {0}")]
+    DuplicateScheduleException(NaiveDate),
+}
+
+impl RestaurantRecord {
+    pub(crate) fn validate(&self) -> Result<(), InvalidRestaurantRecord> {
+        for (field, value) in [
+            ("external_id", &self.external_id),
+            ("name", &self.name),
+            ("maps_url", &self.maps_url),
+        ] {
+            if value.trim().is_empty() {
+                return Err(InvalidRestaurantRecord::EmptyField(field));
+            }
+        }
+        if !(-180.0..=180.0).contains(&self.longitude) {
+            return Err(InvalidRestaurantRecord::Longitude(self.longitude));
+        }
+        if !(-90.0..=90.0).contains(&self.latitude) {
+            return Err(InvalidRestaurantRecord::Latitude(self.latitude));
+        }
+        if self.time_zone.parse::<Tz>().is_err() {
+            return Err(InvalidRestaurantRecord::TimeZone(self.time_zone.clone()));
+        }
+        let working_times = match &self.schedule {
+            Schedule::Regular { working_time } => vec![working_time],
+            Schedule::WithWeekends {
+                weekday_working_time,
+                weekend_working_time,
+            } => vec![weekday_working_time, weekend_working_time],
+            Schedule::Weekly { days, exceptions } => {
+                for (index, exception) in exceptions.iter().enumerate() {
+                    if exceptions[..index]
+                        .iter()
+                        .any(|other| other.date == exception.date)
+                    {
+                        return Err(InvalidRestaurantRecord::DuplicateScheduleException(
+                            exception.date,
+                        ));
+                    }
+                }
+                [
+                    Weekday::Mon,
+                    Weekday::Tue,
+                    Weekday::Wed,
+                    Weekday::Thu,
+                    Weekday::Fri,
+                    Weekday::Sat,
+                    Weekday::Sun,
+                ]
+                .into_iter()
+                .flat_map(|weekday| days.on(weekday))
+                .chain(
+                    exceptions
+                        .iter()
+                        .flat_map(|exception| &exception.working_times),
+                )
+                .collect()
+            }
+        };
+        for WorkingTime {
+            start_time,
+            end_time,
+        } in working_times
+        {
+            if start_time == end_time {
+                return Err(InvalidRestaurantRecord::EmptyWorkingTime(
+                    start_time.to_string(),
+                ));
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        entity::restaurant::{Schedule, ScheduleException, WeeklyWorkingTimes, WorkingTime},
+        model::restaurant_record::{InvalidRestaurantRecord, RestaurantRecord},
+    };
+    use chrono::{NaiveDate, NaiveTime};
+
+    fn record() -> RestaurantRecord {
+        RestaurantRecord {
+            external_id: "naparah".to_owned(),
+            name: "Напарах".to_owned(),
+            maps_url: "https://yandex.ru/maps/-/CDcLmM4d".to_owned(),
+            average_price: "1000-1100 ₽".to_owned(),
+            segment: "₽₽".to_owned(),
+            kitchen: "Европейская, русская".to_owned(),
+            phone_number: "+7xxxxxxxxxx".to_owned(),
+            longitude: 30.299833,
+            latitude: 60.000142,
+            time_zone: "Europe/Moscow".to_owned(),
+            schedule: Schedule::Regular {
+                working_time: WorkingTime {
+                    start_time: NaiveTime::from_hms_opt(8, 0, 0).unwrap(),
+                    end_time: NaiveTime::from_hms_opt(22, 0, 0).unwrap(),
+                },
+            },
+        }
+    }
+
+    #[test]
+    fn valid_record_passes() {
+        assert_eq!(record().validate(), Ok(()));
+    }
+
+    #[test]
+    fn coordinates_out_of_range_are_rejected() {
+        let swapped = RestaurantRecord {
+            longitude: 60.000142,
+            latitude: 130.299833,
+            ..record()
+        };
+
+        assert_eq!(
+            swapped.validate(),
+            Err(InvalidRestaurantRecord::Latitude(130.299833))
+        );
+    }
+
+    #[test]
+    fn empty_working_time_is_rejected() {
+        let time = NaiveTime::from_hms_opt(10, 0, 0).unwrap();
+        let closed = RestaurantRecord {
+            schedule: Schedule::Regular {
+                working_time: WorkingTime {
+                    start_time: time,
+                    end_time: time,
+                },
+            },
+            ..record()
+        };
+
+        assert_eq!(
+            closed.validate(),
+            Err(InvalidRestaurantRecord::EmptyWorkingTime(
+                "10:00:00".to_owned()
+            ))
+        );
+    }
+
+    #[test]
+    fn weekly_schedule_is_checked_including_exceptions() {
+        let time = |hour| NaiveTime::from_hms_opt(hour, 0, 0).unwrap();
+        let new_year = NaiveDate::from_ymd_opt(2025, 1, 1).unwrap();
+        let weekly = |exceptions| RestaurantRecord {
+            schedule: Schedule::Weekly {
+                days: WeeklyWorkingTimes {
+                    monday: vec![WorkingTime {
+                        start_time: time(12),
+                        end_time: time(2),
+                    }],
+                    ..WeeklyWorkingTimes::default()
+                },
+                exceptions,
+            },
+            ..record()
+        };
+        let closed = ScheduleException {
+            date: new_year,
+            working_times: Vec::new(),
+        };
+        let empty = ScheduleException {
+            date: new_year.succ_opt().unwrap(),
+            working_times: vec![WorkingTime {
+                start_time: time(9),
+                end_time: time(9),
+            }],
+        };
+
+        assert_eq!(weekly(vec![closed.clone()]).validate(), Ok(()));
+        assert_eq!(
+            weekly(vec![closed.clone(), closed.clone()]).validate(),
+            Err(InvalidRestaurantRecord::DuplicateScheduleException(
+                new_year
+            ))
+        );
+        assert_eq!(
+            weekly(vec![closed, empty]).validate(),
+            Err(InvalidRestaurantRecord::EmptyWorkingTime(
+                "09:00:00".to_owned()
+            ))
+        );
+    }
+
+    #[test]
+    fn unknown_time_zone_is_rejected() {
+        let record = RestaurantRecord {
+            time_zone: "Europe/Saint_Petersburg".to_owned(),
+            ..record()
+        };
+
+        assert_eq!(
+            record.validate(),
+            Err(InvalidRestaurantRecord::TimeZone(
+                "Europe/Saint_Petersburg".to_owned()
+            ))
+        );
+        assert_eq!(
+            RestaurantRecord {
+                time_zone: "Asia/Vladivostok".to_owned(),
+                ..self::record()
+            }
+            .validate(),
+            Ok(())
+        );
+    }
+}
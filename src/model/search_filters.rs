@@ -0,0 +1,191 @@
+use crate::{
+    config::SearchConfig,
+    entity::{
+        booking_request::{self, PriceBand},
+        restaurant,
+    },
+};
+use sea_orm::Iterable;
+
+/// Kitchens and segments offered as filters, collected from the restaurants
+/// by the registry.
+#[derive(Clone, Default, Debug, PartialEq)]
+pub(crate) struct FilterOptions {
+    pub kitchens: Vec<String>,
+    pub segments: Vec<String>,
+}
+
+impl FilterOptions {
+    /// Splits combined kitchens like "Европейская, русская", the search
+    /// matches each of them separately.
+    pub(crate) fn from_restaurants(restaurants: &[restaurant::Model]) -> Self {
+        let mut kitchens = restaurants
+            .iter()
+            .flat_map(|restaurant| restaurant.kitchen.split(','))
+            .map(capitalize)
+            .filter(|kitchen| !kitchen.is_empty())
+            .collect::<Vec<String>>();
+        kitchens.sort();
+        kitchens.dedup();
+        let mut segments = restaurants
+            .iter()
+            .map(|restaurant| restaurant.segment.clone())
+            .collect::<Vec<String>>();
+        segments.sort();
+        segments.dedup();
+        FilterOptions { kitchens, segments }
+    }
+}
+
+fn capitalize(text: &str) -> String {
+    let mut chars = text.trim().chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
+/// Optional restaurant filters chosen by the user before searching.
+#[derive(Clone, Default, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+pub(crate) struct SearchFilters {
+    pub kitchen: Option<String>,
+    pub segment: Option<String>,
+    pub price_band: Option<PriceBand>,
+}
+
+impl SearchFilters {
+    pub(crate) fn is_empty(&self) -> bool {
+        self.kitchen.is_none() && self.segment.is_none() && self.price_band.is_none()
+    }
+}
+
+impl From<&booking_request::Model> for SearchFilters {
+    fn from(booking_request: &booking_request::Model) -> Self {
+        SearchFilters {
+            kitchen: booking_request.kitchen_filter.clone(),
+            segment: booking_request.segment_filter.clone(),
+            price_band: booking_request.price_band_filter,
+        }
+    }
+}
+
+impl SearchFilters {
+    pub(crate) fn describe(&self, search_config: &SearchConfig) -> String {
+        [
+            self.kitchen
+                .as_ref()
+                .map(|kitchen| format!("кухня {kitchen}")),
+            self.segment
+                .as_ref()
+                .map(|segment| format!("сегмент {segment}")),
+            self.price_band
+                .map(|price_band| format!("средний чек {}", price_band.label(search_config))),
+        ]
+        .into_iter()
+        .flatten()
+        .collect::<Vec<String>>()
+        .join(", ")
+    }
+}
+
+impl PriceBand {
+    pub(crate) fn label(&self, search_config: &SearchConfig) -> String {
+        match self {
+            PriceBand::Low => format!("до {} ₽", search_config.low_price_band_max_price),
+            PriceBand::Medium => format!(
+                "{}–{} ₽",
+                search_config.low_price_band_max_price, search_config.high_price_band_min_price
+            ),
+            PriceBand::High => format!("от {} ₽", search_config.high_price_band_min_price),
+        }
+    }
+
+    pub(crate) fn from_label(label: &str, search_config: &SearchConfig) -> Option<Self> {
+        PriceBand::iter().find(|price_band| price_band.label(search_config) == label)
+    }
+
+    /// Returns the inclusive lower and exclusive upper bounds of the band. A
+    /// restaurant is in the band if its average price range overlaps them.
+    pub(crate) fn bounds(&self, search_config: &SearchConfig) -> (Option<i32>, Option<i32>) {
+        match self {
+            PriceBand::Low => (None, Some(search_config.low_price_band_max_price)),
+            PriceBand::Medium => (
+                Some(search_config.low_price_band_max_price),
+                Some(search_config.high_price_band_min_price),
+            ),
+            PriceBand::High => (Some(search_config.high_price_band_min_price), None),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        config::SearchConfig,
+        entity::{
+            booking_request::PriceBand,
+            restaurant::{self, Schedule, WorkingTime},
+        },
+        model::search_filters::{FilterOptions, SearchFilters},
+    };
+    use chrono::NaiveTime;
+    use sea_orm::Iterable;
+
+    #[test]
+    fn price_band_label_round_trip() {
+        let search_config = SearchConfig::default();
+        for price_band in PriceBand::iter() {
+            assert_eq!(
+                PriceBand::from_label(&price_band.label(&search_config), &search_config),
+                Some(price_band)
+            );
+        }
+        assert_eq!(PriceBand::from_label("Не важно", &search_config), None);
+    }
+
+    #[test]
+    fn filter_options_split_combined_kitchens() {
+        let restaurant = |kitchen: &str, segment: &str| restaurant::Model {
+            id: 1,
+            name: String::new(),
+            maps_url: String::new(),
+            average_price: String::new(),
+            segment: segment.to_owned(),
+            kitchen: kitchen.to_owned(),
+            schedule: Schedule::Regular {
+                working_time: WorkingTime {
+                    start_time: NaiveTime::MIN,
+                    end_time: NaiveTime::MIN,
+                },
+            },
+            score: 100,
+            phone_number: String::new(),
+            external_id: String::new(),
+            time_zone: String::new(),
+        };
+
+        let options = FilterOptions::from_restaurants(&[
+            restaurant("Европейская, русская", "₽₽"),
+            restaurant("Русская", "₽"),
+            restaurant("Грузинская", "₽₽"),
+        ]);
+
+        assert_eq!(options.kitchens, ["Грузинская", "Европейская", "Русская"]);
+        assert_eq!(options.segments, ["₽", "₽₽"]);
+    }
+
+    #[test]
+    fn filters_description() {
+        let filters = SearchFilters {
+            kitchen: Some("Европейская".to_owned()),
+            segment: None,
+            price_band: Some(PriceBand::Low),
+        };
+
+        assert_eq!(
+            filters.describe(&SearchConfig::default()),
+            "кухня Европейская, средний чек до 1000 ₽"
+        );
+        assert!(SearchFilters::default().is_empty());
+    }
+}
@@ -1,4 +1,6 @@
-#[derive(Clone, Default, Debug, serde::Serialize, serde::Deserialize)]
+use super::search_filters::SearchFilters;
+
+#[derive(Clone, Default, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
 pub(crate) enum State {
     #[default]
     Start,
@@ -13,7 +15,26 @@ pub(crate) enum State {
     /// User flow
     ReceiveSearchRequest,
     ReceivePersonNumber,
+    ReceiveSearchRadius {
+        person_number: u8,
+    },
+    ReceiveKitchenFilter {
+        person_number: u8,
+        search_radius: u16,
+    },
+    ReceiveSegmentFilter {
+        person_number: u8,
+        search_radius: u16,
+        filters: SearchFilters,
+    },
+    ReceivePriceBandFilter {
+        person_number: u8,
+        search_radius: u16,
+        filters: SearchFilters,
+    },
     ReceiveLocation {
         person_number: u8,
+        search_radius: u16,
+        filters: SearchFilters,
     },
 }
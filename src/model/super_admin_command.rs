@@ -0,0 +1,14 @@
+use teloxide::utils::command::BotCommands;
+
+/// Commands available to `admin.super_admin_ids` only, each takes the
+/// external id of a restaurant.
+#[derive(BotCommands, Clone)]
+#[command(rename_rule = "snake_case")]
+pub(crate) enum SuperAdminCommand {
+    #[command(description = "Выпустить токен менеджера ресторана")]
+    IssueToken(String),
+    #[command(description = "Отозвать токены менеджеров ресторана и выпустить новый")]
+    RotateToken(String),
+    #[command(description = "Отозвать токены менеджеров ресторана")]
+    RevokeTokens(String),
+}
@@ -1,8 +1,12 @@
-use crate::model::state::State;
+use crate::model::{search_filters::FilterOptions, state::State};
 use anyhow::Result;
-use std::sync::Arc;
+use std::sync::{atomic::AtomicU64, Arc, RwLock};
 use teloxide::{dispatching::dialogue::ErasedStorage, prelude::*};
 
 pub(crate) type MyDialogue = Dialogue<State, ErasedStorage<State>>;
 pub(crate) type HandlerResult = Result<(), Box<dyn std::error::Error + Send + Sync>>;
 pub(crate) type Db<K, T> = Arc<scc::HashMap<K, T>>;
+/// Number of restaurants in the greeting, kept up to date with the registry.
+pub(crate) type RestaurantsNumber = Arc<AtomicU64>;
+/// Search filter options, kept up to date with the registry.
+pub(crate) type SharedFilterOptions = Arc<RwLock<FilterOptions>>;
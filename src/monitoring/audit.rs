@@ -0,0 +1,137 @@
+use crate::{manager_tokens::TokenRejection, model::search_filters::SearchFilters};
+use chrono::{DateTime, Local};
+use serde::Serialize;
+
+/// Log target of the audit records, so the log configuration can route them
+/// to a separate appender.
+pub(crate) const AUDIT_LOG_TARGET: &str = "audit";
+
+/// Booking and manager token events written to the audit log as one JSON
+/// object per line.
+#[derive(Debug, Serialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub(crate) enum AuditEvent<'a> {
+    BookingRequestCreated {
+        booking_request_id: i32,
+        user_chat_id: i64,
+        person_number: u8,
+        search_radius: u16,
+        filters: &'a SearchFilters,
+    },
+    ManagerNotified {
+        booking_request_id: i32,
+        restaurant_id: i32,
+        person_number: u8,
+    },
+    /// A manager answer applies to all pending requests for the same number
+    /// of persons.
+    BookingRequestsAnswered {
+        booking_request_ids: &'a [i32],
+        restaurant_id: i32,
+        person_number: u8,
+        approved: bool,
+        late: bool,
+    },
+    ScoreChanged {
+        restaurant_id: i32,
+        old_score: i32,
+        new_score: i32,
+        reason: ScoreChangeReason,
+    },
+    ManagerTokenIssued {
+        restaurant_id: i32,
+        manager_id: i32,
+        expires_at: DateTime<Local>,
+        actor: TokenActor,
+    },
+    ManagerTokensRevoked {
+        restaurant_id: i32,
+        manager_ids: &'a [i32],
+        actor: TokenActor,
+    },
+    ManagerTokenRedeemed {
+        restaurant_id: i32,
+        manager_id: i32,
+        manager_tg_id: i64,
+    },
+    /// The manager signed out with /sign_out, its row is removed.
+    ManagerSignedOut {
+        restaurant_id: i32,
+        manager_id: i32,
+        manager_tg_id: i64,
+    },
+    /// `manager_id` is not set for unknown tokens.
+    ManagerTokenRejected {
+        manager_id: Option<i32>,
+        user_tg_id: i64,
+        reason: TokenRejection,
+    },
+}
+
+/// Timeliness of the manager answer which changed the score.
+#[derive(Debug, Clone, Copy, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum ScoreChangeReason {
+    InTime,
+    Late,
+    NoAnswer,
+}
+
+/// Who issued or revoked manager tokens.
+#[derive(Debug, Clone, Copy, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub(crate) enum TokenActor {
+    Cli,
+    SuperAdmin { tg_id: i64 },
+}
+
+#[derive(Serialize)]
+struct AuditRecord<'a> {
+    timestamp: DateTime<Local>,
+    #[serde(flatten)]
+    event: AuditEvent<'a>,
+}
+
+pub(crate) fn record(event: AuditEvent) {
+    match format_record(Local::now(), event) {
+        Ok(line) => log::info!(target: AUDIT_LOG_TARGET, "{line}"),
+        Err(err) => log::error!("Failed to serialize audit record: {err}"),
+    }
+}
+
+fn format_record(timestamp: DateTime<Local>, event: AuditEvent) -> serde_json::Result<String> {
+    serde_json::to_string(&AuditRecord { timestamp, event })
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::monitoring::audit::{format_record, AuditEvent, ScoreChangeReason};
+    use chrono::{Local, TimeZone};
+    use serde_json::json;
+
+    #[test]
+    fn record_is_flat_json() {
+        let timestamp = Local.with_ymd_and_hms(2026, 10, 17, 19, 30, 0).unwrap();
+        let line = format_record(
+            timestamp,
+            AuditEvent::ScoreChanged {
+                restaurant_id: 7,
+                old_score: 100,
+                new_score: 97,
+                reason: ScoreChangeReason::NoAnswer,
+            },
+        )
+        .unwrap();
+
+        let record: serde_json::Value = serde_json::from_str(&line).unwrap();
+
+        assert_eq!(record["event"], json!("score_changed"));
+        assert_eq!(record["restaurant_id"], json!(7));
+        assert_eq!(record["new_score"], json!(97));
+        assert_eq!(record["reason"], json!("no_answer"));
+        assert!(record["timestamp"]
+            .as_str()
+            .unwrap()
+            .starts_with("2026-10-17T19:30:00"));
+    }
+}
@@ -0,0 +1,184 @@
+use async_std::future;
+use futures::future::{join_all, BoxFuture};
+use serde::Serialize;
+use std::{
+    collections::BTreeMap,
+    fmt::Display,
+    future::Future,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+/// Time after which a dependency counts as unavailable.
+const CHECK_TIMEOUT: Duration = Duration::from_secs(3);
+
+/// Result of a check with the time it was received.
+type CheckResult = (Instant, Result<(), String>);
+
+type Check = Arc<dyn Fn() -> BoxFuture<'static, Result<(), String>> + Send + Sync>;
+
+/// Named readiness checks of the bot dependencies.
+#[derive(Clone, Default)]
+pub(crate) struct HealthChecks {
+    checks: Vec<(&'static str, Check)>,
+}
+
+#[derive(Debug, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum Status {
+    Ok,
+    Unavailable,
+}
+
+#[derive(Debug, Serialize)]
+pub(crate) struct CheckReport {
+    pub status: Status,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub(crate) struct ReadinessReport {
+    pub status: Status,
+    pub checks: BTreeMap<&'static str, CheckReport>,
+}
+
+impl HealthChecks {
+    pub(crate) fn add<F, Fut, E>(&mut self, name: &'static str, check: F)
+    where
+        F: Fn() -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<(), E>> + Send + 'static,
+        E: Display,
+    {
+        self.checks.push((
+            name,
+            Arc::new(move || {
+                let check = check();
+                Box::pin(async move { check.await.map_err(|err| err.to_string()) })
+            }),
+        ));
+    }
+
+    /// Like [`Self::add`], but reuses the result of the last check for `ttl`.
+    /// Meant for dependencies that should not be called on every probe.
+    pub(crate) fn add_cached<F, Fut, E>(&mut self, name: &'static str, ttl: Duration, check: F)
+    where
+        F: Fn() -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<(), E>> + Send + 'static,
+        E: Display,
+    {
+        let last_result: Arc<Mutex<Option<CheckResult>>> = Arc::default();
+        self.add(name, move || {
+            let cached_result = last_result
+                .lock()
+                .unwrap()
+                .as_ref()
+                .filter(|(checked_at, _)| checked_at.elapsed() < ttl)
+                .map(|(_, result)| result.clone());
+            let check = cached_result.is_none().then(&check);
+            let last_result = last_result.clone();
+            async move {
+                if let Some(result) = cached_result {
+                    return result;
+                }
+                let result = check.unwrap().await.map_err(|err| err.to_string());
+                *last_result.lock().unwrap() = Some((Instant::now(), result.clone()));
+                result
+            }
+        });
+    }
+
+    /// Runs all checks concurrently. The bot is ready if every one passes.
+    pub(crate) async fn check_readiness(&self) -> ReadinessReport {
+        let reports = join_all(self.checks.iter().map(|(name, check)| async move {
+            let result = match future::timeout(CHECK_TIMEOUT, check()).await {
+                Ok(result) => result,
+                Err(_) => Err(format!(
+                    "no response in {} seconds",
+                    CHECK_TIMEOUT.as_secs()
+                )),
+            };
+            let report = match result {
+                Ok(()) => CheckReport {
+                    status: Status::Ok,
+                    error: None,
+                },
+                Err(err) => {
+                    log::warn!("Readiness check {name} failed: {err}");
+                    CheckReport {
+                        status: Status::Unavailable,
+                        error: Some(err),
+                    }
+                }
+            };
+            (*name, report)
+        }))
+        .await;
+        let status = if reports
+            .iter()
+            .all(|(_, report)| report.status == Status::Ok)
+        {
+            Status::Ok
+        } else {
+            Status::Unavailable
+        };
+        ReadinessReport {
+            status,
+            checks: reports.into_iter().collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::monitoring::health::{HealthChecks, Status};
+    use std::{
+        sync::{
+            atomic::{AtomicUsize, Ordering},
+            Arc,
+        },
+        time::Duration,
+    };
+
+    #[tokio::test]
+    async fn failed_check_is_reported_individually() {
+        let mut health_checks = HealthChecks::default();
+        health_checks.add("postgres", || async { Ok::<(), String>(()) });
+        health_checks.add("skytable", || async { Err("connection refused") });
+
+        let report = health_checks.check_readiness().await;
+
+        assert_eq!(report.status, Status::Unavailable);
+        assert_eq!(report.checks["postgres"].status, Status::Ok);
+        assert_eq!(
+            report.checks["skytable"].error.as_deref(),
+            Some("connection refused")
+        );
+        assert_eq!(
+            serde_json::to_value(&report).unwrap()["checks"]["postgres"],
+            serde_json::json!({ "status": "ok" })
+        );
+    }
+
+    #[tokio::test]
+    async fn cached_check_runs_once_per_ttl() {
+        let calls = Arc::new(AtomicUsize::new(0));
+        let mut health_checks = HealthChecks::default();
+        {
+            let calls = calls.clone();
+            health_checks.add_cached("telegram", Duration::from_secs(60), move || {
+                calls.fetch_add(1, Ordering::SeqCst);
+                async { Err("unauthorized") }
+            });
+        }
+
+        health_checks.check_readiness().await;
+        let report = health_checks.check_readiness().await;
+
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+        assert_eq!(
+            report.checks["telegram"].error.as_deref(),
+            Some("unauthorized")
+        );
+    }
+}
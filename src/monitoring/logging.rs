@@ -0,0 +1,28 @@
+use crate::{config::LoggingConfig, monitoring::audit::AUDIT_LOG_TARGET};
+use anyhow::{Context, Result};
+use log::LevelFilter;
+use log4rs::{
+    append::console::ConsoleAppender,
+    config::{Appender, Config, Logger, Root},
+};
+
+/// Initializes logging from the log4rs configuration file if it is set, or
+/// to the console at `default_level` otherwise. Audit records go to the
+/// console at the info level in the latter case.
+pub(crate) fn init(config: &LoggingConfig, default_level: LevelFilter) -> Result<()> {
+    match &config.config_file {
+        Some(path) => log4rs::init_file(path, Default::default())
+            .with_context(|| format!("failed to load log configuration from {}", path.display()))?,
+        None => {
+            let stdout = ConsoleAppender::builder().build();
+
+            let log_config = Config::builder()
+                .appender(Appender::builder().build("stdout", Box::new(stdout)))
+                .logger(Logger::builder().build(AUDIT_LOG_TARGET, LevelFilter::Info))
+                .build(Root::builder().appender("stdout").build(default_level))?;
+
+            log4rs::init_config(log_config)?;
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,144 @@
+use lazy_static::lazy_static;
+use prometheus::{
+    register_histogram, register_histogram_vec, register_int_counter, register_int_counter_vec,
+    register_int_gauge, Encoder, Histogram, HistogramVec, IntCounter, IntCounterVec, IntGauge,
+    TextEncoder,
+};
+use std::time::Duration;
+use tokio::sync::mpsc;
+
+lazy_static! {
+    static ref SEARCHES: IntCounterVec = register_int_counter_vec!(
+        "mest_net_searches_total",
+        "Booking requests sent by users, by party size",
+        &["party_size"]
+    )
+    .unwrap();
+    static ref RESTAURANTS_NOTIFIED: Histogram = register_histogram!(
+        "mest_net_restaurants_notified_per_search",
+        "Restaurants asked about free places in one search round",
+        vec![0.0, 1.0, 2.0, 3.0, 5.0, 10.0, 20.0, 50.0]
+    )
+    .unwrap();
+    static ref MANAGER_ANSWERS: IntCounterVec = register_int_counter_vec!(
+        "mest_net_manager_answers_total",
+        "Manager answers to booking requests by timeliness: in_time, late or no_answer",
+        &["timeliness"]
+    )
+    .unwrap();
+    static ref MANAGER_ANSWER_LATENCY: HistogramVec = register_histogram_vec!(
+        "mest_net_manager_answer_latency_seconds",
+        "Time from a booking request notification to the manager answer",
+        &["timeliness"],
+        vec![5.0, 15.0, 30.0, 60.0, 120.0, 300.0, 600.0, 1800.0]
+    )
+    .unwrap();
+    static ref APPROVALS: IntCounter = register_int_counter!(
+        "mest_net_approvals_total",
+        "Booking requests approved by managers"
+    )
+    .unwrap();
+    static ref EMPTY_RESULTS: IntCounter = register_int_counter!(
+        "mest_net_empty_results_total",
+        "Searches that ended without free places"
+    )
+    .unwrap();
+    static ref COMMAND_CHANNEL_DEPTH: IntGauge = register_int_gauge!(
+        "mest_net_command_channel_depth",
+        "Search commands waiting to be dispatched to restaurants"
+    )
+    .unwrap();
+    static ref DIALOGUE_STORAGE_ERRORS: IntCounterVec = register_int_counter_vec!(
+        "mest_net_dialogue_storage_errors_total",
+        "Failed dialogue storage operations",
+        &["operation"]
+    )
+    .unwrap();
+    static ref DATABASE_ERRORS: IntCounterVec = register_int_counter_vec!(
+        "mest_net_database_errors_total",
+        "Failed database calls by kind: unavailable or query",
+        &["kind"]
+    )
+    .unwrap();
+}
+
+pub(crate) fn record_search(person_number: u8) {
+    SEARCHES
+        .with_label_values(&[&person_number.to_string()])
+        .inc();
+}
+
+pub(crate) fn record_restaurants_notified(restaurants_number: usize) {
+    RESTAURANTS_NOTIFIED.observe(restaurants_number as f64);
+}
+
+/// Records a manager answer given `latency` after the notification.
+pub(crate) fn record_manager_answer(in_time: bool, latency: Duration) {
+    let timeliness = if in_time { "in_time" } else { "late" };
+    MANAGER_ANSWERS.with_label_values(&[timeliness]).inc();
+    MANAGER_ANSWER_LATENCY
+        .with_label_values(&[timeliness])
+        .observe(latency.as_secs_f64());
+}
+
+pub(crate) fn record_missed_answers(missed_answers_number: usize) {
+    MANAGER_ANSWERS
+        .with_label_values(&["no_answer"])
+        .inc_by(missed_answers_number as u64);
+}
+
+pub(crate) fn record_approval() {
+    APPROVALS.inc();
+}
+
+pub(crate) fn record_empty_result() {
+    EMPTY_RESULTS.inc();
+}
+
+/// Records the number of queued commands as seen by the sending side.
+pub(crate) fn record_command_channel_depth<T>(sender: &mpsc::Sender<T>) {
+    set_command_channel_depth(sender.max_capacity() - sender.capacity());
+}
+
+pub(crate) fn set_command_channel_depth(depth: usize) {
+    COMMAND_CHANNEL_DEPTH.set(depth as i64);
+}
+
+pub(crate) fn record_dialogue_storage_error(operation: &str) {
+    DIALOGUE_STORAGE_ERRORS
+        .with_label_values(&[operation])
+        .inc();
+}
+
+pub(crate) fn record_database_error(kind: &str) {
+    DATABASE_ERRORS.with_label_values(&[kind]).inc();
+}
+
+/// Renders all metrics in the Prometheus text format.
+pub(crate) fn render() -> String {
+    let mut buffer = Vec::new();
+    if let Err(err) = TextEncoder::new().encode(&prometheus::gather(), &mut buffer) {
+        log::error!("{err}");
+    }
+    String::from_utf8(buffer).unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::monitoring::metrics::{record_manager_answer, record_search, render};
+    use std::time::Duration;
+
+    #[test]
+    fn recorded_metrics_are_rendered() {
+        record_search(4);
+        record_manager_answer(false, Duration::from_secs(200));
+
+        let rendered = render();
+
+        assert!(rendered.contains("mest_net_searches_total{party_size=\"4\"}"));
+        assert!(rendered.contains("mest_net_manager_answers_total{timeliness=\"late\"}"));
+        assert!(rendered.contains(
+            "mest_net_manager_answer_latency_seconds_bucket{timeliness=\"late\",le=\"300\"}"
+        ));
+    }
+}
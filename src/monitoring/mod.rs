@@ -0,0 +1,5 @@
+pub(crate) mod audit;
+pub(crate) mod health;
+pub(crate) mod logging;
+pub(crate) mod metrics;
+pub(crate) mod server;
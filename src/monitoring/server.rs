@@ -0,0 +1,56 @@
+use crate::{
+    background_processing::shutdown::Shutdown,
+    monitoring::{
+        health::{HealthChecks, Status},
+        metrics,
+    },
+};
+use anyhow::Result;
+use axum::{
+    extract::State,
+    http::{header, StatusCode},
+    response::IntoResponse,
+    routing::get,
+    Json, Router,
+};
+use std::net::SocketAddr;
+
+/// Serves the metrics and the health endpoints on `listen_address` until the
+/// shutdown.
+pub(crate) async fn serve(
+    listen_address: SocketAddr,
+    health_checks: HealthChecks,
+    shutdown: Shutdown,
+) -> Result<()> {
+    let app = Router::new()
+        .route("/metrics", get(render_metrics))
+        .route("/health/live", get(check_liveness))
+        .route("/health/ready", get(check_readiness))
+        .with_state(health_checks);
+    log::info!("Serving metrics and health checks on {listen_address}");
+    axum::Server::try_bind(&listen_address)?
+        .serve(app.into_make_service())
+        .with_graceful_shutdown(async move { shutdown.triggered().await })
+        .await?;
+    Ok(())
+}
+
+async fn render_metrics() -> impl IntoResponse {
+    (
+        [(header::CONTENT_TYPE, prometheus::TEXT_FORMAT)],
+        metrics::render(),
+    )
+}
+
+async fn check_liveness() -> impl IntoResponse {
+    Json(serde_json::json!({ "status": Status::Ok }))
+}
+
+async fn check_readiness(State(health_checks): State<HealthChecks>) -> impl IntoResponse {
+    let report = health_checks.check_readiness().await;
+    let status_code = match report.status {
+        Status::Ok => StatusCode::OK,
+        Status::Unavailable => StatusCode::SERVICE_UNAVAILABLE,
+    };
+    (status_code, Json(report))
+}
@@ -1,29 +1,56 @@
 use crate::{
-    background_processing::tasks::wait_for_restaurants_response,
-    db::DatabaseHandler,
+    background_processing::{
+        shutdown::Shutdown,
+        tasks::{
+            cancel_booking_request, request_expiration_duration, resolve_person_noun_form,
+            wait_for_restaurants_response,
+        },
+    },
+    config::AppConfig,
+    db::{DatabaseError, DatabaseHandler},
+    entity::booking_request::{BookingRequestStatus, PriceBand},
+    manager_tokens::{
+        self, check_redeemable, hash_token, ManagerTokenAction, TokenActionOutcome, TokenRejection,
+    },
     model::{
+        booking_event::BookingEvent,
         booking_info::BookingInfo,
         bot_command::BotCommand,
+        callback_data::CallbackData,
         mest_check_command::MestCheckCommand,
+        search_filters::SearchFilters,
         state::State::{self, Start},
+        super_admin_command::SuperAdminCommand,
         types::*,
     },
+    monitoring::{
+        audit::{self, AuditEvent, ScoreChangeReason, TokenActor},
+        metrics,
+    },
     utils::{
         constants::{
-            BOOKING_EXPIRATION_MINUTES, FEEDBACK_FORM_URL, IN_TIME_ANSWER_BONUS,
-            MAX_RESTAURANT_SCORE, MAX_SUPPORTED_PERSONS, MIN_RESTAURANT_SCORE,
-            MIN_SUPPORTED_PERSONS, NOT_IN_TIME_ANSWER_PENALTY, SEARCH_REQUEST_MESSAGE,
+            ANY_FILTER_VALUE_MESSAGE, LARGE_GROUP_REQUEST_MESSAGE, NO_FILTERS_REQUEST_MESSAGE,
+            SEARCH_REQUEST_MESSAGE, SERVICE_UNAVAILABLE_MESSAGE,
         },
+        distance::parse_search_radius,
         keyboard::*,
     },
 };
 use chrono::Local;
 use sea_orm::{ActiveValue::Set, IntoActiveModel};
-use std::time::Duration;
+use std::{
+    ops::ControlFlow,
+    sync::{atomic::Ordering, Arc},
+    time::Duration,
+};
 use teloxide::{
     dispatching::{dialogue, dialogue::ErasedStorage, UpdateHandler},
+    dptree::{
+        di::{DependencyMap, DependencySupplier},
+        Cont,
+    },
     prelude::*,
-    types::{ParseMode, ReplyMarkup},
+    types::{ParseMode, ReplyMarkup, UpdateKind},
     utils::command::BotCommands,
 };
 use tokio::sync::{broadcast, mpsc};
@@ -37,12 +64,23 @@ pub(crate) fn schema() -> UpdateHandler<Box<dyn std::error::Error + Send + Sync>
                 .branch(case![BotCommand::Help].endpoint(help))
                 .branch(case![BotCommand::Start].endpoint(start))
                 .branch(case![BotCommand::Reset].endpoint(reset))
+                .branch(case![BotCommand::SignOut].endpoint(sign_out))
                 .branch(case![BotCommand::Feedback].endpoint(feedback))
+                .branch(case![BotCommand::Cancel].endpoint(cancel))
                 .branch(dptree::endpoint(invalid_input)),
         )
         .branch(case![BotCommand::Reset].endpoint(reset))
-        .branch(case![BotCommand::Feedback].endpoint(feedback));
+        .branch(case![BotCommand::SignOut].endpoint(sign_out))
+        .branch(case![BotCommand::Feedback].endpoint(feedback))
+        .branch(case![BotCommand::Cancel].endpoint(cancel));
+    let super_admin_command_handler = teloxide::filter_command::<SuperAdminCommand, _>()
+        .filter(|msg: Message, config: Arc<AppConfig>| {
+            msg.from()
+                .is_some_and(|user| config.admin.super_admin_ids.contains(&(user.id.0 as i64)))
+        })
+        .endpoint(super_admin_command);
     let message_handler = Update::filter_message()
+        .branch(super_admin_command_handler)
         .branch(command_handler)
         .branch(case![State::RoleSelection].endpoint(receive_role_selection))
         // Admin flow
@@ -54,10 +92,102 @@ pub(crate) fn schema() -> UpdateHandler<Box<dyn std::error::Error + Send + Sync>
         //  User flow
         .branch(case![State::ReceiveSearchRequest].endpoint(receive_search_request))
         .branch(case![State::ReceivePersonNumber].endpoint(receive_person_number))
-        .branch(case![State::ReceiveLocation { person_number }].endpoint(receive_location))
+        .branch(case![State::ReceiveSearchRadius { person_number }].endpoint(receive_search_radius))
+        .branch(
+            case![State::ReceiveKitchenFilter {
+                person_number,
+                search_radius
+            }]
+            .endpoint(receive_kitchen_filter),
+        )
+        .branch(
+            case![State::ReceiveSegmentFilter {
+                person_number,
+                search_radius,
+                filters
+            }]
+            .endpoint(receive_segment_filter),
+        )
+        .branch(
+            case![State::ReceivePriceBandFilter {
+                person_number,
+                search_radius,
+                filters
+            }]
+            .endpoint(receive_price_band_filter),
+        )
+        .branch(
+            case![State::ReceiveLocation {
+                person_number,
+                search_radius,
+                filters
+            }]
+            .endpoint(receive_location),
+        )
         .branch(dptree::endpoint(invalid_input));
 
-    dialogue::enter::<Update, ErasedStorage<State>, State, _>().branch(message_handler)
+    let callback_query_handler = Update::filter_callback_query()
+        .filter_map(|q: CallbackQuery| q.data.and_then(|data| data.parse::<CallbackData>().ok()))
+        .branch(
+            case![CallbackData::BookingAnswer {
+                booking_request_id,
+                approved
+            }]
+            .endpoint(receive_booking_answer),
+        )
+        .branch(
+            case![CallbackData::RestaurantSelection {
+                booking_request_id,
+                restaurant_id
+            }]
+            .endpoint(receive_restaurant_selection),
+        )
+        .branch(
+            case![CallbackData::CancelSearch { booking_request_id }]
+                .endpoint(receive_search_cancellation),
+        );
+
+    dptree::from_fn(report_database_errors).chain(
+        dialogue::enter::<Update, ErasedStorage<State>, State, _>()
+            .branch(message_handler)
+            .branch(callback_query_handler),
+    )
+}
+
+/// Tells the user that the service is temporarily unavailable when a handler
+/// fails with a [`DatabaseError`]. The error is passed on to the dispatcher
+/// error handler to be logged.
+async fn report_database_errors(
+    deps: DependencyMap,
+    cont: Cont<'static, DependencyMap, HandlerResult>,
+) -> ControlFlow<HandlerResult, DependencyMap> {
+    let bot: Arc<Bot> = deps.get();
+    let update: Arc<Update> = deps.get();
+    let result = cont(deps).await;
+    if let ControlFlow::Break(Err(err)) = &result {
+        if let Some(err) = err.downcast_ref::<DatabaseError>() {
+            metrics::record_database_error(err.kind());
+            let reported = match &update.kind {
+                UpdateKind::CallbackQuery(q) => bot
+                    .answer_callback_query(q.id.clone())
+                    .text(SERVICE_UNAVAILABLE_MESSAGE)
+                    .show_alert(true)
+                    .await
+                    .map(|_| ()),
+                _ => match update.chat() {
+                    Some(chat) => bot
+                        .send_message(chat.id, SERVICE_UNAVAILABLE_MESSAGE)
+                        .await
+                        .map(|_| ()),
+                    None => Ok(()),
+                },
+            };
+            if let Err(err) = reported {
+                log::error!("{err}");
+            }
+        }
+    }
+    result
 }
 
 async fn invalid_input(bot: Bot, msg: Message) -> HandlerResult {
@@ -81,22 +211,8 @@ async fn start(bot: Bot, dialogue: MyDialogue, msg: Message) -> HandlerResult {
     Ok(())
 }
 
-async fn reset(
-    db_handler: DatabaseHandler,
-    bot: Bot,
-    dialogue: MyDialogue,
-    msg: Message,
-) -> HandlerResult {
-    if let Some(manager) = db_handler
-        .find_manager_by_tg_id(msg.from().unwrap().id.0 as i64)
-        .await
-    {
-        let mut manager = manager.into_active_model();
-        manager.tg_id = Set(None);
-        manager.share_contact = Set(false);
-        db_handler.update_manager(manager).await?;
-    }
-
+/// Resets the dialogue only, a manager stays signed in to the restaurant.
+async fn reset(bot: Bot, dialogue: MyDialogue, msg: Message) -> HandlerResult {
     bot.send_message(msg.chat.id, BotCommand::descriptions().to_string())
         .reply_markup(ReplyMarkup::kb_remove())
         .await?;
@@ -105,23 +221,174 @@ async fn reset(
     Ok(())
 }
 
-async fn feedback(bot: Bot, _dialogue: MyDialogue, msg: Message) -> HandlerResult {
+/// Removes the manager, so that the restaurant is not searched until a new
+/// token is redeemed.
+async fn sign_out(
+    db_handler: DatabaseHandler,
+    bot: Bot,
+    dialogue: MyDialogue,
+    msg: Message,
+) -> HandlerResult {
+    let user_tg_id = msg.from().unwrap().id.0 as i64;
+    let Some(manager) = db_handler.find_manager_by_tg_id(user_tg_id).await? else {
+        bot.send_message(msg.chat.id, "Вы не администратор ресторана")
+            .await?;
+        return Ok(());
+    };
+    if db_handler.delete_manager(manager.id).await? {
+        audit::record(AuditEvent::ManagerSignedOut {
+            restaurant_id: manager.restaurant_id,
+            manager_id: manager.id,
+            manager_tg_id: user_tg_id,
+        });
+    }
     bot.send_message(
         msg.chat.id,
-        format!(
-            "Поделиться обратной связью вы можете, заполнив следующую <a href=\"{}\">форму</a>",
-            FEEDBACK_FORM_URL
-        ),
+        "Вы вышли из администраторов ресторана. Чтобы войти снова, попросите команду Mest Net \
+         выпустить новый токен",
     )
-    .parse_mode(ParseMode::Html)
+    .reply_markup(ReplyMarkup::kb_remove())
     .await?;
+
+    dialogue.exit().await?;
+    Ok(())
+}
+
+async fn super_admin_command(
+    db_handler: DatabaseHandler,
+    config: Arc<AppConfig>,
+    bot: Bot,
+    msg: Message,
+    command: SuperAdminCommand,
+) -> HandlerResult {
+    let (action, external_id) = match &command {
+        SuperAdminCommand::IssueToken(external_id) => (ManagerTokenAction::Issue, external_id),
+        SuperAdminCommand::RotateToken(external_id) => (ManagerTokenAction::Rotate, external_id),
+        SuperAdminCommand::RevokeTokens(external_id) => (ManagerTokenAction::Revoke, external_id),
+    };
+    let external_id = external_id.trim();
+    if external_id.is_empty() {
+        bot.send_message(
+            msg.chat.id,
+            "Укажите идентификатор ресторана, например /issue_token naparah",
+        )
+        .await?;
+        return Ok(());
+    }
+    let Some(restaurant) = db_handler
+        .find_restaurant_by_external_id(external_id)
+        .await?
+    else {
+        bot.send_message(msg.chat.id, format!("Ресторан {external_id} не найден"))
+            .await?;
+        return Ok(());
+    };
+
+    let actor = TokenActor::SuperAdmin {
+        tg_id: msg.from().unwrap().id.0 as i64,
+    };
+    let text = match manager_tokens::apply(
+        &db_handler,
+        restaurant.id,
+        action,
+        config.admin.manager_token_ttl_hours,
+        actor,
+    )
+    .await?
+    {
+        TokenActionOutcome::Issued(issued) => format!(
+            "Токен менеджера ресторана {}: <code>{}</code>\nДействует до {} и может быть \
+             использован один раз",
+            restaurant.name,
+            issued.token,
+            issued
+                .expires_at
+                .with_timezone(&restaurant.time_zone())
+                .format("%d.%m.%Y %H:%M")
+        ),
+        TokenActionOutcome::Revoked => {
+            format!("Токены менеджеров ресторана {} отозваны", restaurant.name)
+        }
+        TokenActionOutcome::ManagerSignedIn => format!(
+            "У ресторана {} уже есть администратор. Чтобы заменить его, используйте /rotate_token \
+             {}",
+            restaurant.name, restaurant.external_id
+        ),
+    };
+    bot.send_message(msg.chat.id, text)
+        .parse_mode(ParseMode::Html)
+        .await?;
+    Ok(())
+}
+
+async fn feedback(
+    config: Arc<AppConfig>,
+    bot: Bot,
+    _dialogue: MyDialogue,
+    msg: Message,
+) -> HandlerResult {
+    match &config.telegram.feedback_form_url {
+        Some(feedback_form_url) => {
+            bot.send_message(
+                msg.chat.id,
+                format!(
+                    "Поделиться обратной связью вы можете, заполнив следующую <a \
+                     href=\"{}\">форму</a>",
+                    feedback_form_url
+                ),
+            )
+            .parse_mode(ParseMode::Html)
+            .await?;
+        }
+        None => {
+            bot.send_message(msg.chat.id, "Форма обратной связи пока недоступна")
+                .await?;
+        }
+    }
+    Ok(())
+}
+
+async fn cancel(
+    config: Arc<AppConfig>,
+    restaurants_booking_info: Db<i32, BookingInfo>,
+    db_handler: DatabaseHandler,
+    sender: broadcast::Sender<BookingEvent>,
+    bot: Bot,
+    msg: Message,
+) -> HandlerResult {
+    let cancelled = match db_handler
+        .find_pending_booking_request_by_user_chat_id(msg.chat.id.0)
+        .await?
+    {
+        Some(booking_request) => {
+            cancel_booking_request(
+                bot.clone(),
+                db_handler,
+                restaurants_booking_info,
+                sender,
+                booking_request,
+                &config.booking,
+            )
+            .await?
+        }
+        None => false,
+    };
+    if cancelled {
+        bot.send_message(msg.chat.id, "Поиск мест отменен")
+            .reply_markup(make_search_keyboard())
+            .await?;
+    } else {
+        bot.send_message(msg.chat.id, "Нет активного поиска мест")
+            .await?;
+    }
     Ok(())
 }
 
 /// STATE HANDLERS
 
 async fn receive_role_selection(
-    restaurants_number: u64,
+    db_handler: DatabaseHandler,
+    restaurants_number: RestaurantsNumber,
     bot: Bot,
     dialogue: MyDialogue,
     msg: Message,
@@ -130,7 +397,10 @@ async fn receive_role_selection(
         Some("Обычный пользователь") => {
             bot.send_message(
                 msg.chat.id,
-                format!(include_str!("resources/greetings.txt"), restaurants_number),
+                format!(
+                    include_str!("resources/greetings.txt"),
+                    restaurants_number.load(Ordering::Relaxed)
+                ),
             )
             .reply_markup(make_search_keyboard())
             .parse_mode(ParseMode::Html)
@@ -138,6 +408,21 @@ async fn receive_role_selection(
             dialogue.update(State::ReceiveSearchRequest).await?;
         }
         Some("Администратор") => {
+            // After /reset a signed-in manager has no token to enter again
+            if db_handler
+                .find_manager_by_tg_id(msg.from().unwrap().id.0 as i64)
+                .await?
+                .is_some()
+            {
+                bot.send_message(
+                    msg.chat.id,
+                    "Вы уже администратор ресторана, ожидайте запросы на бронирование",
+                )
+                .reply_markup(ReplyMarkup::kb_remove())
+                .await?;
+                dialogue.update(State::WaitingForRequests).await?;
+                return Ok(());
+            }
             bot.send_message(
                 msg.chat.id,
                 include_str!("resources/greetings_for_admin.txt"),
@@ -161,49 +446,100 @@ async fn receive_admin_token(
     dialogue: MyDialogue,
     msg: Message,
 ) -> HandlerResult {
-    match msg.text() {
-        Some(token) => match db_handler.find_manager_by_token(token.to_string()).await {
-            Some(token_manager) => {
-                let token_manager_id = token_manager.id;
-                let mut token_manager = token_manager.into_active_model();
-                match db_handler
-                    .find_manager_by_tg_id(msg.from().unwrap().id.0 as i64)
-                    .await
+    let Some(token) = msg.text() else {
+        bot.send_message(msg.chat.id, "Отправьте токен").await?;
+        return Ok(());
+    };
+    let user_tg_id = msg.from().unwrap().id.0 as i64;
+    let (manager_id, reason) = match db_handler
+        .find_manager_by_token_hash(hash_token(token))
+        .await?
+    {
+        None => (None, TokenRejection::Unknown),
+        Some(token_manager) => match check_redeemable(&token_manager, Local::now()) {
+            Err(reason) => (Some(token_manager.id), reason),
+            Ok(()) => match db_handler.find_manager_by_tg_id(user_tg_id).await? {
+                Some(manager) if manager.restaurant_id == token_manager.restaurant_id => {
+                    bot.send_message(
+                        msg.chat.id,
+                        "Вы уже администратор этого ресторана, ожидайте запросы на бронирование",
+                    )
+                    .await?;
+                    dialogue.update(State::WaitingForRequests).await?;
+                    return Ok(());
+                }
+                Some(_) => {
+                    bot.send_message(
+                        msg.chat.id,
+                        "Нельзя быть администратором более чем в одном ресторане",
+                    )
+                    .await?;
+                    return Ok(());
+                }
+                None if db_handler
+                    .redeem_manager_token(token_manager.id, user_tg_id)
+                    .await? =>
                 {
-                    Some(tg_id_manager) if token_manager_id != tg_id_manager.id => {
-                        bot.send_message(
-                            msg.chat.id,
-                            "Нельзя быть администратором более чем в одном ресторане",
-                        )
-                        .await?;
-                    }
-                    _ => {
-                        if token_manager.tg_id.unwrap().is_none() {
-                            token_manager.tg_id = Set(Some(msg.from().unwrap().id.0 as i64));
-                            db_handler.update_manager(token_manager).await?;
-                            bot.send_message(
-                                msg.chat.id,
-                                "Делиться вашим контактом с пользователями для бронирования?",
-                            )
-                            .reply_markup(make_answer_keyboard())
-                            .await?;
-                            dialogue.update(State::ReceiveShareContactAllowance).await?
-                        } else {
-                            bot.send_message(
-                                msg.chat.id,
-                                include_str!("resources/admin_already_authorized.txt"),
-                            )
-                            .await?;
-                        }
-                    }
+                    audit::record(AuditEvent::ManagerTokenRedeemed {
+                        restaurant_id: token_manager.restaurant_id,
+                        manager_id: token_manager.id,
+                        manager_tg_id: user_tg_id,
+                    });
+                    bot.send_message(
+                        msg.chat.id,
+                        "Делиться вашим контактом с пользователями для бронирования?",
+                    )
+                    .reply_markup(make_answer_keyboard())
+                    .await?;
+                    dialogue.update(State::ReceiveShareContactAllowance).await?;
+                    return Ok(());
                 }
-            }
-            _ => {
-                bot.send_message(msg.chat.id, "Неверный токен").await?;
-            }
+                // Redeemed by someone else in the meantime, or another manager
+                // of the restaurant has signed in
+                None => {
+                    let reason = if db_handler
+                        .has_signed_in_manager(token_manager.restaurant_id)
+                        .await?
+                    {
+                        TokenRejection::ManagerSignedIn
+                    } else {
+                        TokenRejection::Used
+                    };
+                    (Some(token_manager.id), reason)
+                }
+            },
         },
-        _ => {
-            bot.send_message(msg.chat.id, "Отправьте токен").await?;
+    };
+    audit::record(AuditEvent::ManagerTokenRejected {
+        manager_id,
+        user_tg_id,
+        reason,
+    });
+    match reason {
+        TokenRejection::Unknown => {
+            bot.send_message(msg.chat.id, "Неверный токен").await?;
+        }
+        TokenRejection::Used => {
+            bot.send_message(
+                msg.chat.id,
+                include_str!("resources/admin_already_authorized.txt"),
+            )
+            .await?;
+        }
+        TokenRejection::Expired => {
+            bot.send_message(
+                msg.chat.id,
+                "Срок действия токена истёк, попросите команду Mest Net выпустить новый",
+            )
+            .await?;
+        }
+        TokenRejection::ManagerSignedIn => {
+            bot.send_message(
+                msg.chat.id,
+                "У ресторана уже есть администратор. Если он сменился, попросите команду Mest Net \
+                 выпустить новый токен",
+            )
+            .await?;
         }
     }
 
@@ -220,7 +556,7 @@ async fn receive_share_contact_allowance(
         Some(ans) if ans == "Да" || ans == "Нет" => {
             if let Some(manager) = db_handler
                 .find_manager_by_tg_id(msg.from().unwrap().id.0 as i64)
-                .await
+                .await?
             {
                 let mut manager = manager.into_active_model();
                 manager.share_contact = Set(ans == "Да");
@@ -238,109 +574,337 @@ async fn receive_share_contact_allowance(
     Ok(())
 }
 
-async fn receive_booking_request(
+async fn receive_booking_request(bot: Bot, msg: Message) -> HandlerResult {
+    bot.send_message(
+        msg.chat.id,
+        "Отвечайте на запросы кнопками под сообщением с запросом",
+    )
+    .await?;
+    Ok(())
+}
+
+async fn receive_booking_answer(
+    config: Arc<AppConfig>,
     restaurants_booking_info: Db<i32, BookingInfo>,
     db_handler: DatabaseHandler,
-    sender: broadcast::Sender<(i32, bool, u8)>,
+    sender: broadcast::Sender<BookingEvent>,
     bot: Bot,
-    _dialogue: MyDialogue,
-    msg: Message,
+    (booking_request_id, approved): (i32, bool),
+    q: CallbackQuery,
 ) -> HandlerResult {
-    if let Some(reply_to_message) = msg.reply_to_message() {
-        if let Some(text) = reply_to_message.text() {
-            if !text.starts_with("У вас есть места на")
-                || reply_to_message.from().unwrap().id == msg.from().unwrap().id
-            {
-                bot.send_message(msg.chat.id, "Выбрано неподходящее сообщение для Reply")
-                    .await?;
-                return Ok(());
-            }
-            let tokens = text.split_ascii_whitespace().collect::<Vec<&str>>();
-            if let Ok(person_number) = tokens[tokens.len() - 2].parse::<u8>() {
-                match msg.text() {
-                    Some(ans) if ans == "Да" || ans == "Нет" => {
-                        if let Some(manager) = db_handler
-                            .find_manager_by_tg_id(msg.from().unwrap().id.0 as i64)
-                            .await
-                        {
-                            if let Some(mut booking_info) = restaurants_booking_info
-                                .get_async(&manager.restaurant_id)
-                                .await
-                            {
-                                if ans == "Да" {
-                                    booking_info.booking_state |= 1 << person_number;
-                                    booking_info.set_booking_expiration_time(
-                                        (person_number - 1) as usize,
-                                        Local::now()
-                                            + Duration::from_secs(BOOKING_EXPIRATION_MINUTES * 60),
-                                    );
-                                }
-                                if let Some(restaurant) = db_handler
-                                    .find_restaurant_by_id(manager.restaurant_id)
-                                    .await
-                                {
-                                    let booking_request_expiration_time = booking_info
-                                        .get_booking_request_expiration_time(
-                                            (person_number - 1) as usize,
-                                        );
-                                    let mut score = restaurant.score;
-                                    if booking_info.notifications_state & (1 << person_number) != 0
-                                    {
-                                        if Local::now() > *booking_request_expiration_time {
-                                            score = (score - NOT_IN_TIME_ANSWER_PENALTY)
-                                                .max(MIN_RESTAURANT_SCORE);
-                                        } else {
-                                            score = (score + IN_TIME_ANSWER_BONUS)
-                                                .min(MAX_RESTAURANT_SCORE);
-                                        }
-                                    }
-                                    if score != restaurant.score {
-                                        db_handler
-                                            .update_restaurant_score_wiht_raw_sql(
-                                                restaurant.id,
-                                                score,
-                                            )
-                                            .await?;
-                                    }
-                                }
-                                log::info!(
-                                    "{} manager with username = {:?} and user_id = {} {} booking \
-                                     request for {} persons",
-                                    booking_info.restaurant_name,
-                                    msg.from().unwrap().username,
-                                    msg.from().unwrap().id,
-                                    if ans == "Да" { "approved" } else { "reject" },
-                                    person_number
-                                );
-                                booking_info.notifications_state &= !(1 << person_number);
-                                if let Err(err) =
-                                    sender.send((manager.restaurant_id, ans == "Да", person_number))
-                                {
-                                    log::error!("{err}");
-                                }
-                                bot.send_message(msg.chat.id, "Спасибо за ваш ответ")
-                                    .await?;
-                            }
-                        }
-                    }
-                    _ => {
-                        bot.send_message(msg.chat.id, "Ответьте Да или Нет").await?;
-                    }
-                }
-            }
+    let manager = db_handler.find_manager_by_tg_id(q.from.id.0 as i64).await?;
+    let booking_request = db_handler
+        .find_booking_request_by_id(booking_request_id)
+        .await?;
+    let (manager, booking_request) = match (manager, booking_request) {
+        (Some(manager), Some(booking_request)) => (manager, booking_request),
+        _ => {
+            bot.answer_callback_query(q.id)
+                .text("Запрос не найден")
+                .await?;
+            return Ok(());
         }
-    } else {
-        bot.send_message(msg.chat.id, "Отправьте ответ исполоьзуя Reply")
+    };
+    // Only requests sent to the restaurant of the manager can be answered
+    if db_handler
+        .find_booking_request_answer(booking_request.id, manager.restaurant_id)
+        .await?
+        .is_none()
+    {
+        log::warn!(
+            "Manager with id = {} answered booking request with id = {} not sent to their \
+             restaurant",
+            manager.id,
+            booking_request.id
+        );
+        bot.answer_callback_query(q.id)
+            .text("Запрос не найден")
+            .await?;
+        return Ok(());
+    }
+    let Some(restaurant) = db_handler
+        .find_restaurant_by_id(manager.restaurant_id)
+        .await?
+    else {
+        bot.answer_callback_query(q.id)
+            .text("Запрос не найден")
+            .await?;
+        return Ok(());
+    };
+    let person_number = booking_request.person_number as u8;
+    // The registry may have dropped the restaurant on a reload in the meantime
+    let mut booking_info = restaurants_booking_info
+        .entry_async(restaurant.id)
+        .await
+        .or_insert_with(|| BookingInfo::new(restaurant.name.clone()));
+
+    if approved {
+        metrics::record_approval();
+    }
+    let scoring_config = &config.scoring;
+    let mut late = false;
+    let mut score = restaurant.score;
+    let mut reason = ScoreChangeReason::InTime;
+    if let Some(booking_request_expiration_time) =
+        booking_info.get_booking_request_expiration_time(person_number)
+    {
+        let current_time = Local::now();
+        let notification_time =
+            *booking_request_expiration_time - request_expiration_duration(&config.booking);
+        late = current_time > *booking_request_expiration_time;
+        metrics::record_manager_answer(
+            !late,
+            (current_time - notification_time)
+                .to_std()
+                .unwrap_or_default(),
+        );
+        if late {
+            reason = ScoreChangeReason::Late;
+            score = (score - scoring_config.not_in_time_answer_penalty)
+                .max(scoring_config.min_restaurant_score);
+        } else {
+            score = (score + scoring_config.in_time_answer_bonus)
+                .min(scoring_config.max_restaurant_score);
+        }
+    }
+    if score != restaurant.score {
+        db_handler
+            .update_restaurant_score_wiht_raw_sql(restaurant.id, score)
             .await?;
+        audit::record(AuditEvent::ScoreChanged {
+            restaurant_id: restaurant.id,
+            old_score: restaurant.score,
+            new_score: score,
+            reason,
+        });
+    }
+    log::info!(
+        "{} manager {} booking requests for {} persons",
+        restaurant.name,
+        if approved { "approved" } else { "rejected" },
+        person_number
+    );
+    booking_info.remove_booking_request(person_number);
+    drop(booking_info);
+    db_handler
+        .delete_booking_notification(restaurant.id, person_number)
+        .await?;
+    let answered_booking_requests_ids = db_handler
+        .answer_pending_booking_requests(restaurant.id, person_number, approved)
+        .await?;
+    // Holding the places only makes sense while somebody is still waiting for
+    // them, e.g. not after the request expired
+    if approved && !answered_booking_requests_ids.is_empty() {
+        let booking_expiration_time =
+            Local::now() + Duration::from_secs(config.booking.booking_expiration_minutes * 60);
+        restaurants_booking_info
+            .entry_async(restaurant.id)
+            .await
+            .or_insert_with(|| BookingInfo::new(restaurant.name.clone()))
+            .set_booking_expiration_time(person_number, booking_expiration_time);
+        db_handler
+            .save_booking_hold(restaurant.id, person_number, booking_expiration_time)
+            .await?;
+    }
+    audit::record(AuditEvent::BookingRequestsAnswered {
+        booking_request_ids: &answered_booking_requests_ids,
+        restaurant_id: restaurant.id,
+        person_number,
+        approved,
+        late,
+    });
+    for booking_request_id in answered_booking_requests_ids {
+        if let Err(err) = sender.send(BookingEvent::RequestAnswered {
+            booking_request_id,
+            restaurant_id: restaurant.id,
+        }) {
+            log::error!("{err}");
+        }
+    }
+
+    bot.answer_callback_query(q.id)
+        .text("Спасибо за ваш ответ")
+        .await?;
+    if let Some(notification) = &q.message {
+        if let Some(text) = notification.text() {
+            bot.edit_message_text(
+                notification.chat.id,
+                notification.id,
+                format!(
+                    "{}\n\nВаш ответ: {}",
+                    text,
+                    if approved { "Да" } else { "Нет" }
+                ),
+            )
+            .await?;
+        }
     }
     Ok(())
 }
 
-async fn receive_search_request(bot: Bot, dialogue: MyDialogue, msg: Message) -> HandlerResult {
+async fn receive_restaurant_selection(
+    restaurants_booking_info: Db<i32, BookingInfo>,
+    db_handler: DatabaseHandler,
+    bot: Bot,
+    (booking_request_id, restaurant_id): (i32, i32),
+    q: CallbackQuery,
+) -> HandlerResult {
+    let booking_request = match db_handler
+        .find_booking_request_by_id(booking_request_id)
+        .await?
+    {
+        Some(booking_request)
+            if booking_request.user_chat_id == q.from.id.0 as i64
+                && booking_request.status == BookingRequestStatus::Answered =>
+        {
+            booking_request
+        }
+        _ => {
+            bot.answer_callback_query(q.id)
+                .text("Запрос больше не актуален")
+                .await?;
+            return Ok(());
+        }
+    };
+    let person_number = booking_request.person_number as u8;
+    let approved_restaurants_ids = db_handler
+        .find_booking_request_answers(booking_request_id)
+        .await?
+        .into_iter()
+        .filter(|answer| answer.approved == Some(true))
+        .map(|answer| answer.restaurant_id)
+        .collect::<Vec<i32>>();
+    if !approved_restaurants_ids.contains(&restaurant_id) {
+        bot.answer_callback_query(q.id)
+            .text("Ресторан не подтвердил наличие мест")
+            .await?;
+        return Ok(());
+    }
+
+    // A repeated or concurrent selection must not notify the managers twice
+    if !db_handler
+        .fulfill_answered_booking_request(booking_request_id)
+        .await?
+    {
+        bot.answer_callback_query(q.id)
+            .text("Запрос больше не актуален")
+            .await?;
+        return Ok(());
+    }
+
+    let person_noun_form = resolve_person_noun_form(person_number);
+    let mut selected_restaurant_name = String::new();
+    for restaurant in db_handler
+        .find_restaurants_by_ids(approved_restaurants_ids)
+        .await?
+    {
+        let selected = restaurant.id == restaurant_id;
+        // The approval of another restaurant is shared by all open requests
+        // for the same number of persons, other users may still select it
+        if !selected
+            && db_handler
+                .has_approved_open_booking_requests(restaurant.id, person_number)
+                .await?
+        {
+            continue;
+        }
+        if let Some(mut booking_info) = restaurants_booking_info.get_async(&restaurant.id).await {
+            booking_info.remove_booking(person_number);
+        }
+        db_handler
+            .delete_booking_hold(restaurant.id, person_number)
+            .await?;
+        let manager_notification = if selected {
+            selected_restaurant_name = restaurant.name.clone();
+            format!("К вам придут гости: компания на {person_number} {person_noun_form}")
+        } else {
+            format!(
+                "Места на {person_number} {person_noun_form} больше не нужны, гости выбрали \
+                 другой ресторан"
+            )
+        };
+        if let Err(err) = bot
+            .send_message(
+                UserId(restaurant.manager_tg_id as u64),
+                manager_notification,
+            )
+            .await
+        {
+            log::error!("{err}");
+        }
+    }
+
+    log::info!(
+        "User with user_id = {} selected restaurant with id = {} for booking request with id = {}",
+        q.from.id,
+        restaurant_id,
+        booking_request_id
+    );
+
+    bot.answer_callback_query(q.id).await?;
+    if let Some(results) = &q.message {
+        bot.edit_message_reply_markup(results.chat.id, results.id)
+            .await?;
+    }
+    bot.send_message(
+        ChatId(booking_request.user_chat_id),
+        format!("Мы предупредили ресторан {selected_restaurant_name} о вашем визите"),
+    )
+    .reply_markup(make_search_keyboard())
+    .await?;
+    Ok(())
+}
+
+async fn receive_search_cancellation(
+    config: Arc<AppConfig>,
+    restaurants_booking_info: Db<i32, BookingInfo>,
+    db_handler: DatabaseHandler,
+    sender: broadcast::Sender<BookingEvent>,
+    bot: Bot,
+    booking_request_id: i32,
+    q: CallbackQuery,
+) -> HandlerResult {
+    let cancelled = match db_handler
+        .find_booking_request_by_id(booking_request_id)
+        .await?
+    {
+        Some(booking_request) if booking_request.user_chat_id == q.from.id.0 as i64 => {
+            cancel_booking_request(
+                bot.clone(),
+                db_handler,
+                restaurants_booking_info,
+                sender,
+                booking_request,
+                &config.booking,
+            )
+            .await?
+        }
+        _ => false,
+    };
+    if !cancelled {
+        bot.answer_callback_query(q.id)
+            .text("Поиск уже завершен")
+            .await?;
+        return Ok(());
+    }
+
+    bot.answer_callback_query(q.id).await?;
+    bot.send_message(q.from.id, "Вы можете начать новый поиск")
+        .reply_markup(make_search_keyboard())
+        .await?;
+    Ok(())
+}
+
+async fn receive_search_request(
+    config: Arc<AppConfig>,
+    bot: Bot,
+    dialogue: MyDialogue,
+    msg: Message,
+) -> HandlerResult {
     match msg.text() {
         Some(SEARCH_REQUEST_MESSAGE) => {
             bot.send_message(msg.chat.id, "Сколько гостей будет?")
-                .reply_markup(make_number_keyboard())
+                .reply_markup(make_number_keyboard(&config.booking))
                 .await?;
             dialogue.update(State::ReceivePersonNumber).await?;
         }
@@ -353,83 +917,320 @@ async fn receive_search_request(bot: Bot, dialogue: MyDialogue, msg: Message) ->
     Ok(())
 }
 
-async fn receive_person_number(bot: Bot, dialogue: MyDialogue, msg: Message) -> HandlerResult {
-    match msg.text().map(|text| text.parse::<u8>()) {
-        Some(Ok(person_number))
-            if (MIN_SUPPORTED_PERSONS..=MAX_SUPPORTED_PERSONS).contains(&person_number) =>
-        {
-            bot.send_message(msg.chat.id, "Отправьте локацию для поиска мест")
-                .reply_markup(make_location_keyboard())
-                .await?;
-            dialogue
-                .update(State::ReceiveLocation { person_number })
-                .await?;
-        }
-        _ => {
+async fn receive_person_number(
+    config: Arc<AppConfig>,
+    bot: Bot,
+    dialogue: MyDialogue,
+    msg: Message,
+) -> HandlerResult {
+    let booking_config = &config.booking;
+    match msg.text() {
+        Some(LARGE_GROUP_REQUEST_MESSAGE) => {
             bot.send_message(
                 msg.chat.id,
                 format!(
-                    "Отправьте число от {} до {}",
-                    MIN_SUPPORTED_PERSONS, MAX_SUPPORTED_PERSONS
+                    "Отправьте число гостей от {} до {}",
+                    booking_config.large_group_min_persons, booking_config.max_supported_persons
                 ),
             )
+            .reply_markup(ReplyMarkup::kb_remove())
             .await?;
         }
+        text => match text.map(|text| text.trim().parse::<u8>()) {
+            Some(Ok(person_number))
+                if (booking_config.min_supported_persons
+                    ..=booking_config.max_supported_persons)
+                    .contains(&person_number) =>
+            {
+                let reply = if person_number >= booking_config.large_group_min_persons {
+                    "Рестораны получат запрос для большой компании. В каком радиусе искать места?"
+                } else {
+                    "В каком радиусе искать места?"
+                };
+                bot.send_message(msg.chat.id, reply)
+                    .reply_markup(make_search_radius_keyboard(&config.search))
+                    .await?;
+                dialogue
+                    .update(State::ReceiveSearchRadius { person_number })
+                    .await?;
+            }
+            _ => {
+                bot.send_message(
+                    msg.chat.id,
+                    format!(
+                        "Отправьте число от {} до {}",
+                        booking_config.min_supported_persons, booking_config.max_supported_persons
+                    ),
+                )
+                .await?;
+            }
+        },
+    }
+
+    Ok(())
+}
+
+async fn receive_search_radius(
+    config: Arc<AppConfig>,
+    filter_options: SharedFilterOptions,
+    bot: Bot,
+    dialogue: MyDialogue,
+    person_number: u8,
+    msg: Message,
+) -> HandlerResult {
+    match msg
+        .text()
+        .and_then(|text| parse_search_radius(text, &config.search))
+    {
+        Some(search_radius) => {
+            let kitchens = filter_options.read().unwrap().kitchens.clone();
+            bot.send_message(msg.chat.id, "Какую кухню предпочитаете?")
+                .reply_markup(make_kitchen_filter_keyboard(&kitchens))
+                .await?;
+            dialogue
+                .update(State::ReceiveKitchenFilter {
+                    person_number,
+                    search_radius,
+                })
+                .await?;
+        }
+        None => {
+            bot.send_message(msg.chat.id, "Выберите радиус поиска кнопкой")
+                .await?;
+        }
     }
 
     Ok(())
 }
 
+async fn receive_kitchen_filter(
+    filter_options: SharedFilterOptions,
+    bot: Bot,
+    dialogue: MyDialogue,
+    (person_number, search_radius): (u8, u16),
+    msg: Message,
+) -> HandlerResult {
+    let kitchen = match msg.text() {
+        Some(NO_FILTERS_REQUEST_MESSAGE) => {
+            bot.send_message(msg.chat.id, "Отправьте локацию для поиска мест")
+                .reply_markup(make_location_keyboard())
+                .await?;
+            dialogue
+                .update(State::ReceiveLocation {
+                    person_number,
+                    search_radius,
+                    filters: SearchFilters::default(),
+                })
+                .await?;
+            return Ok(());
+        }
+        Some(ANY_FILTER_VALUE_MESSAGE) => None,
+        Some(text)
+            if filter_options
+                .read()
+                .unwrap()
+                .kitchens
+                .iter()
+                .any(|k| k == text) =>
+        {
+            Some(text.to_owned())
+        }
+        _ => {
+            bot.send_message(msg.chat.id, "Выберите кухню кнопкой")
+                .await?;
+            return Ok(());
+        }
+    };
+
+    let segments = filter_options.read().unwrap().segments.clone();
+    bot.send_message(msg.chat.id, "Какой ценовой сегмент?")
+        .reply_markup(make_segment_filter_keyboard(&segments))
+        .await?;
+    dialogue
+        .update(State::ReceiveSegmentFilter {
+            person_number,
+            search_radius,
+            filters: SearchFilters {
+                kitchen,
+                ..Default::default()
+            },
+        })
+        .await?;
+    Ok(())
+}
+
+async fn receive_segment_filter(
+    config: Arc<AppConfig>,
+    filter_options: SharedFilterOptions,
+    bot: Bot,
+    dialogue: MyDialogue,
+    (person_number, search_radius, filters): (u8, u16, SearchFilters),
+    msg: Message,
+) -> HandlerResult {
+    let segment = match msg.text() {
+        Some(ANY_FILTER_VALUE_MESSAGE) => None,
+        Some(text)
+            if filter_options
+                .read()
+                .unwrap()
+                .segments
+                .iter()
+                .any(|s| s == text) =>
+        {
+            Some(text.to_owned())
+        }
+        _ => {
+            bot.send_message(msg.chat.id, "Выберите сегмент кнопкой")
+                .await?;
+            return Ok(());
+        }
+    };
+
+    bot.send_message(msg.chat.id, "Какой средний чек?")
+        .reply_markup(make_price_band_filter_keyboard(&config.search))
+        .await?;
+    dialogue
+        .update(State::ReceivePriceBandFilter {
+            person_number,
+            search_radius,
+            filters: SearchFilters { segment, ..filters },
+        })
+        .await?;
+    Ok(())
+}
+
+async fn receive_price_band_filter(
+    config: Arc<AppConfig>,
+    bot: Bot,
+    dialogue: MyDialogue,
+    (person_number, search_radius, filters): (u8, u16, SearchFilters),
+    msg: Message,
+) -> HandlerResult {
+    let price_band = match msg.text() {
+        Some(ANY_FILTER_VALUE_MESSAGE) => None,
+        text => match text.and_then(|text| PriceBand::from_label(text, &config.search)) {
+            Some(price_band) => Some(price_band),
+            None => {
+                bot.send_message(msg.chat.id, "Выберите средний чек кнопкой")
+                    .await?;
+                return Ok(());
+            }
+        },
+    };
+
+    bot.send_message(msg.chat.id, "Отправьте локацию для поиска мест")
+        .reply_markup(make_location_keyboard())
+        .await?;
+    dialogue
+        .update(State::ReceiveLocation {
+            person_number,
+            search_radius,
+            filters: SearchFilters {
+                price_band,
+                ..filters
+            },
+        })
+        .await?;
+    Ok(())
+}
+
+#[allow(clippy::too_many_arguments)]
 async fn receive_location(
-    restaurants_booking_info: Db<i32, BookingInfo>,
+    config: Arc<AppConfig>,
+    shutdown: Shutdown,
     command_sender: mpsc::Sender<MestCheckCommand>,
-    answer_sender: broadcast::Sender<(i32, bool, u8)>,
+    booking_event_sender: broadcast::Sender<BookingEvent>,
     db_handler: DatabaseHandler,
     bot: Bot,
     dialogue: MyDialogue,
-    person_number: u8,
+    (person_number, search_radius, filters): (u8, u16, SearchFilters),
     msg: Message,
 ) -> HandlerResult {
+    if shutdown.is_triggered() {
+        bot.send_message(
+            msg.chat.id,
+            "Бот перезапускается, отправьте локацию еще раз через пару минут",
+        )
+        .await?;
+        return Ok(());
+    }
     match msg.location() {
         Some(location) => {
-            bot.send_message(
-                msg.chat.id,
-                "В ближайшие к вам рестораны был отправлен запрос, ожидайте ответа",
-            )
-            .reply_markup(make_search_keyboard())
-            .await?;
+            let booking_request = db_handler
+                .create_booking_request(
+                    msg.chat.id.0,
+                    person_number,
+                    location.longitude,
+                    location.latitude,
+                    search_radius,
+                    filters.clone(),
+                )
+                .await?;
 
-            let mest_check_command =
-                MestCheckCommand::new(person_number, location.longitude, location.latitude);
+            let results_message = bot
+                .send_message(
+                    msg.chat.id,
+                    "В ближайшие к вам рестораны отправляется запрос, ожидайте ответа",
+                )
+                .reply_markup(make_cancel_search_keyboard(booking_request.id))
+                .await?;
+
+            let mest_check_command = MestCheckCommand::new(booking_request.id);
             {
                 let bot = bot.clone();
-                let msg = msg.clone();
-                let mest_check_command = mest_check_command.clone();
-                tokio::spawn(async move {
-                    wait_for_restaurants_response(
-                        bot,
-                        msg.chat.id,
-                        answer_sender.subscribe(),
+                let command_sender = command_sender.clone();
+                let booking_event_receiver = booking_event_sender.subscribe();
+                let booking_request = booking_request.clone();
+                let task_shutdown = shutdown.clone();
+                shutdown.spawn(async move {
+                    if let Err(err) = wait_for_restaurants_response(
+                        bot.clone(),
+                        results_message.id,
+                        command_sender,
+                        booking_event_receiver,
                         db_handler.clone(),
-                        mest_check_command,
-                        restaurants_booking_info,
+                        booking_request,
+                        config,
+                        task_shutdown,
                     )
                     .await
+                    {
+                        log::error!("{err}");
+                        if let Some(err) = err.downcast_ref::<DatabaseError>() {
+                            metrics::record_database_error(err.kind());
+                            if let Err(err) = bot
+                                .edit_message_text(
+                                    results_message.chat.id,
+                                    results_message.id,
+                                    SERVICE_UNAVAILABLE_MESSAGE,
+                                )
+                                .await
+                            {
+                                log::error!("{err}");
+                            }
+                        }
+                    }
                 });
             }
 
-            if let Err(err) = command_sender.send(mest_check_command.clone()).await {
+            if let Err(err) = command_sender.send(mest_check_command).await {
                 log::error!("{err}")
             } else {
+                metrics::record_search(person_number);
+                metrics::record_command_channel_depth(&command_sender);
                 log::info!(
-                    "User with username = {:?} and user_id = {} send booking request for {} \
-                     persons at location with latitude = {} and longitude = {}",
-                    msg.from().unwrap().username,
-                    msg.from().unwrap().id,
+                    "Booking request with id = {} for {} persons sent in radius of {} meters",
+                    booking_request.id,
                     person_number,
-                    mest_check_command.latitude,
-                    mest_check_command.longitude
-                )
+                    search_radius
+                );
+                audit::record(AuditEvent::BookingRequestCreated {
+                    booking_request_id: booking_request.id,
+                    user_chat_id: booking_request.user_chat_id,
+                    person_number,
+                    search_radius,
+                    filters: &filters,
+                });
             };
 
             dialogue.update(State::ReceiveSearchRequest).await?;
@@ -442,3 +1243,421 @@ async fn receive_location(
 
     Ok(())
 }
+
+/// End-to-end scenarios. They need `TEST_DATABASE_URL` pointing to a PostGIS
+/// database with the migrations applied, so they are ignored by default.
+#[cfg(test)]
+mod tests {
+    use crate::{
+        model::{callback_data::CallbackData, search_filters::SearchFilters},
+        testing::{
+            fake_bot_api::BotApiRequest,
+            test_bot::{TestBot, SUPER_ADMIN_ID},
+        },
+    };
+
+    /// Signs in as an administrator with `token` and returns the reply to it.
+    async fn send_manager_token(bot: &TestBot, manager_id: i64, token: &str) -> BotApiRequest {
+        bot.send_text(manager_id, "/start");
+        let reply = bot.next_message(manager_id).await;
+        assert_eq!(reply.text(), "Какая у вас роль?");
+        assert_eq!(reply.buttons(), ["Обычный пользователь", "Администратор"]);
+
+        bot.send_text(manager_id, "Администратор");
+        let reply = bot.next_message(manager_id).await;
+        assert_eq!(
+            reply.text(),
+            include_str!("resources/greetings_for_admin.txt")
+        );
+
+        bot.send_text(manager_id, token);
+        bot.next_message(manager_id).await
+    }
+
+    /// Token from the reply to a super admin token command.
+    fn issued_token(reply: &BotApiRequest) -> String {
+        reply
+            .text()
+            .split_once("<code>")
+            .and_then(|(_, rest)| rest.split_once("</code>"))
+            .map(|(token, _)| token.to_owned())
+            .unwrap_or_else(|| panic!("no token in {reply:#?}"))
+    }
+
+    async fn authorize_manager(bot: &TestBot, manager_id: i64) {
+        let reply = send_manager_token(bot, manager_id, &bot.restaurant.manager_token).await;
+        assert_eq!(
+            reply.text(),
+            "Делиться вашим контактом с пользователями для бронирования?"
+        );
+
+        bot.send_text(manager_id, "Да");
+        let reply = bot.next_message(manager_id).await;
+        assert_eq!(reply.text(), "Ожидайте запросы на бронирование");
+    }
+
+    /// Goes through the user flow up to the sent location, searching around
+    /// the test restaurant.
+    async fn search_places(bot: &TestBot, user_id: i64) {
+        bot.send_text(user_id, "/start");
+        bot.next_message(user_id).await;
+        bot.send_text(user_id, "Обычный пользователь");
+        let reply = bot.next_message(user_id).await;
+        assert_eq!(reply.buttons(), ["Найти места"]);
+
+        bot.send_text(user_id, "Найти места");
+        let reply = bot.next_message(user_id).await;
+        assert_eq!(reply.text(), "Сколько гостей будет?");
+
+        bot.send_text(user_id, "2");
+        let reply = bot.next_message(user_id).await;
+        assert_eq!(reply.text(), "В каком радиусе искать места?");
+        assert_eq!(reply.buttons(), ["500 м", "1 км", "3 км"]);
+
+        bot.send_text(user_id, "500 м");
+        let reply = bot.next_message(user_id).await;
+        assert_eq!(reply.text(), "Какую кухню предпочитаете?");
+
+        bot.send_text(user_id, "Искать без фильтров");
+        let reply = bot.next_message(user_id).await;
+        assert_eq!(reply.text(), "Отправьте локацию для поиска мест");
+
+        bot.send_location(
+            user_id,
+            bot.restaurant.longitude,
+            bot.restaurant.latitude + 0.001,
+        );
+        let reply = bot.next_message(user_id).await;
+        assert_eq!(
+            reply.text(),
+            "В ближайшие к вам рестораны отправляется запрос, ожидайте ответа"
+        );
+    }
+
+    #[tokio::test]
+    #[ignore = "needs TEST_DATABASE_URL"]
+    async fn wrong_manager_token_is_rejected() {
+        let bot = TestBot::start().await;
+        let manager_id = bot.new_user_id();
+
+        let reply = send_manager_token(&bot, manager_id, "not a token").await;
+        assert_eq!(reply.text(), "Неверный токен");
+
+        bot.stop().await;
+    }
+
+    #[tokio::test]
+    #[ignore = "needs TEST_DATABASE_URL"]
+    async fn super_admin_rotates_single_use_manager_token() {
+        let bot = TestBot::start().await;
+        let old_manager_id = bot.new_user_id();
+        let new_manager_id = bot.new_user_id();
+        let other_user_id = bot.new_user_id();
+        authorize_manager(&bot, old_manager_id).await;
+
+        bot.send_text(
+            other_user_id,
+            &format!("/rotate_token {}", bot.restaurant.external_id),
+        );
+        let reply = bot.next_message(other_user_id).await;
+        assert_eq!(reply.text(), "Please, send /start.");
+
+        bot.send_text(
+            SUPER_ADMIN_ID,
+            &format!("/rotate_token {}", bot.restaurant.external_id),
+        );
+        let reply = bot.next_message(SUPER_ADMIN_ID).await;
+        let token = issued_token(&reply);
+
+        let reply = send_manager_token(&bot, new_manager_id, &bot.restaurant.manager_token).await;
+        assert_eq!(reply.text(), "Неверный токен");
+        bot.send_text(new_manager_id, &token);
+        let reply = bot.next_message(new_manager_id).await;
+        assert_eq!(
+            reply.text(),
+            "Делиться вашим контактом с пользователями для бронирования?"
+        );
+        let reply = send_manager_token(&bot, other_user_id, &token).await;
+        assert_eq!(
+            reply.text(),
+            include_str!("resources/admin_already_authorized.txt")
+        );
+        assert!(bot
+            .db_handler
+            .find_manager_by_tg_id(old_manager_id)
+            .await
+            .unwrap()
+            .is_none());
+
+        bot.send_text(SUPER_ADMIN_ID, "/revoke_tokens no-such-restaurant");
+        let reply = bot.next_message(SUPER_ADMIN_ID).await;
+        assert_eq!(reply.text(), "Ресторан no-such-restaurant не найден");
+
+        bot.stop().await;
+    }
+
+    #[tokio::test]
+    #[ignore = "needs TEST_DATABASE_URL"]
+    async fn restaurant_has_single_signed_in_manager() {
+        let bot = TestBot::start().await;
+        let manager_id = bot.new_user_id();
+        let other_manager_id = bot.new_user_id();
+        bot.send_text(
+            SUPER_ADMIN_ID,
+            &format!("/issue_token {}", bot.restaurant.external_id),
+        );
+        let reply = bot.next_message(SUPER_ADMIN_ID).await;
+        let other_token = issued_token(&reply);
+        authorize_manager(&bot, manager_id).await;
+
+        let reply = send_manager_token(&bot, other_manager_id, &other_token).await;
+        assert!(reply
+            .text()
+            .starts_with("У ресторана уже есть администратор"));
+        bot.send_text(
+            SUPER_ADMIN_ID,
+            &format!("/issue_token {}", bot.restaurant.external_id),
+        );
+        let reply = bot.next_message(SUPER_ADMIN_ID).await;
+        assert!(reply.text().contains("уже есть администратор"));
+
+        bot.stop().await;
+    }
+
+    #[tokio::test]
+    #[ignore = "needs TEST_DATABASE_URL"]
+    async fn manager_stays_signed_in_after_reset_until_sign_out() {
+        let bot = TestBot::start().await;
+        let manager_id = bot.new_user_id();
+        authorize_manager(&bot, manager_id).await;
+
+        bot.send_text(manager_id, "/reset");
+        bot.next_message(manager_id).await;
+        assert!(bot
+            .db_handler
+            .find_manager_by_tg_id(manager_id)
+            .await
+            .unwrap()
+            .is_some());
+        bot.send_text(manager_id, "/start");
+        bot.next_message(manager_id).await;
+        bot.send_text(manager_id, "Администратор");
+        let reply = bot.next_message(manager_id).await;
+        assert_eq!(
+            reply.text(),
+            "Вы уже администратор ресторана, ожидайте запросы на бронирование"
+        );
+
+        bot.send_text(manager_id, "/sign_out");
+        let reply = bot.next_message(manager_id).await;
+        assert!(reply
+            .text()
+            .starts_with("Вы вышли из администраторов ресторана"));
+        assert!(bot
+            .db_handler
+            .find_manager_by_tg_id(manager_id)
+            .await
+            .unwrap()
+            .is_none());
+
+        let reply = send_manager_token(&bot, manager_id, &bot.restaurant.manager_token).await;
+        assert_eq!(reply.text(), "Неверный токен");
+
+        bot.stop().await;
+    }
+
+    #[tokio::test]
+    #[ignore = "needs TEST_DATABASE_URL"]
+    async fn user_books_places_approved_by_manager() {
+        let bot = TestBot::start().await;
+        let manager_id = bot.new_user_id();
+        let user_id = bot.new_user_id();
+        authorize_manager(&bot, manager_id).await;
+
+        search_places(&bot, user_id).await;
+        let notification = bot.next_message(manager_id).await;
+        assert_eq!(notification.text(), "У вас есть места на 2 персоны?");
+
+        bot.press_button(manager_id, &notification, "Да");
+        let answer = bot.next_callback_answer().await;
+        assert_eq!(answer.text(), "Спасибо за ваш ответ");
+        let notification = bot.next_edit(manager_id).await;
+        assert_eq!(
+            notification.text(),
+            "У вас есть места на 2 персоны?\n\nВаш ответ: Да"
+        );
+
+        let results = bot
+            .api
+            .take_request("editMessageText", |request| {
+                request.chat_id() == Some(user_id)
+                    && request.text().starts_with("Список ресторанов")
+            })
+            .await;
+        let selection = format!("Пойдем в {}", bot.restaurant.name);
+        assert_eq!(results.buttons(), [selection.clone()]);
+
+        bot.press_button(user_id, &results, &selection);
+        let reply = bot.next_message(manager_id).await;
+        assert_eq!(reply.text(), "К вам придут гости: компания на 2 персоны");
+        let reply = bot.next_message(user_id).await;
+        assert_eq!(
+            reply.text(),
+            format!(
+                "Мы предупредили ресторан {} о вашем визите",
+                bot.restaurant.name
+            )
+        );
+
+        bot.next_callback_answer().await;
+        bot.press_button(user_id, &results, &selection);
+        let answer = bot.next_callback_answer().await;
+        assert_eq!(answer.text(), "Запрос больше не актуален");
+
+        bot.stop().await;
+    }
+
+    #[tokio::test]
+    #[ignore = "needs TEST_DATABASE_URL"]
+    async fn answer_is_recorded_for_restaurant_missing_in_registry() {
+        let bot = TestBot::start().await;
+        let manager_id = bot.new_user_id();
+        let user_id = bot.new_user_id();
+        authorize_manager(&bot, manager_id).await;
+
+        search_places(&bot, user_id).await;
+        let notification = bot.next_message(manager_id).await;
+        bot.restaurants_booking_info.remove(&bot.restaurant.id);
+        bot.press_button(manager_id, &notification, "Да");
+        let answer = bot.next_callback_answer().await;
+        assert_eq!(answer.text(), "Спасибо за ваш ответ");
+
+        let results = bot
+            .api
+            .take_request("editMessageText", |request| {
+                request.chat_id() == Some(user_id)
+                    && request.text().starts_with("Список ресторанов")
+            })
+            .await;
+        assert_eq!(
+            results.buttons(),
+            [format!("Пойдем в {}", bot.restaurant.name)]
+        );
+
+        bot.stop().await;
+    }
+
+    #[tokio::test]
+    #[ignore = "needs TEST_DATABASE_URL"]
+    async fn manager_cannot_answer_request_not_sent_to_restaurant() {
+        let bot = TestBot::start().await;
+        let manager_id = bot.new_user_id();
+        authorize_manager(&bot, manager_id).await;
+        let booking_request = bot
+            .db_handler
+            .create_booking_request(
+                bot.new_user_id(),
+                2,
+                bot.restaurant.longitude,
+                bot.restaurant.latitude,
+                500,
+                SearchFilters::default(),
+            )
+            .await
+            .unwrap();
+
+        bot.send_text(manager_id, "Да");
+        let reply = bot.next_message(manager_id).await;
+        bot.send_callback_data(
+            manager_id,
+            &reply,
+            &CallbackData::BookingAnswer {
+                booking_request_id: booking_request.id,
+                approved: true,
+            }
+            .to_string(),
+        );
+        let answer = bot.next_callback_answer().await;
+        assert_eq!(answer.text(), "Запрос не найден");
+        let answers = bot
+            .db_handler
+            .find_booking_request_answers(booking_request.id)
+            .await
+            .unwrap();
+        assert!(answers.is_empty());
+
+        bot.stop().await;
+    }
+
+    #[tokio::test]
+    #[ignore = "needs TEST_DATABASE_URL"]
+    async fn late_approval_does_not_hold_places() {
+        let bot = TestBot::start().await;
+        let manager_id = bot.new_user_id();
+        authorize_manager(&bot, manager_id).await;
+        let booking_request = bot
+            .db_handler
+            .create_booking_request(
+                bot.new_user_id(),
+                2,
+                bot.restaurant.longitude,
+                bot.restaurant.latitude,
+                500,
+                SearchFilters::default(),
+            )
+            .await
+            .unwrap();
+        bot.db_handler
+            .create_booking_request_answer(booking_request.id, bot.restaurant.id, Some(false))
+            .await
+            .unwrap();
+
+        bot.send_text(manager_id, "Да");
+        let reply = bot.next_message(manager_id).await;
+        bot.send_callback_data(
+            manager_id,
+            &reply,
+            &CallbackData::BookingAnswer {
+                booking_request_id: booking_request.id,
+                approved: true,
+            }
+            .to_string(),
+        );
+        let answer = bot.next_callback_answer().await;
+        assert_eq!(answer.text(), "Спасибо за ваш ответ");
+        assert!(bot
+            .restaurants_booking_info
+            .get_async(&bot.restaurant.id)
+            .await
+            .and_then(|booking_info| booking_info.get_booking_expiration_time(2).copied())
+            .is_none());
+
+        bot.stop().await;
+    }
+
+    #[tokio::test]
+    #[ignore = "needs TEST_DATABASE_URL"]
+    async fn search_rejected_by_manager_ends_without_places() {
+        let bot = TestBot::start().await;
+        let manager_id = bot.new_user_id();
+        let user_id = bot.new_user_id();
+        authorize_manager(&bot, manager_id).await;
+
+        search_places(&bot, user_id).await;
+        let notification = bot.next_message(manager_id).await;
+        bot.press_button(manager_id, &notification, "Нет");
+        bot.next_callback_answer().await;
+
+        bot.api
+            .take_request("editMessageText", |request| {
+                request.chat_id() == Some(user_id)
+                    && request.text() == "К сожалению, мест на 2 персоны нет"
+            })
+            .await;
+        let reply = bot.next_message(user_id).await;
+        assert_eq!(reply.text(), "Вы можете начать новый поиск");
+        assert_eq!(reply.buttons(), ["Найти места"]);
+
+        bot.stop().await;
+    }
+}
@@ -0,0 +1,269 @@
+use async_std::{future::timeout, task};
+use axum::{
+    body::Bytes,
+    extract::{Path, State},
+    routing::post,
+    Json, Router,
+};
+use serde_json::{json, Value};
+use std::{
+    net::{SocketAddr, TcpListener},
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+use teloxide::Bot;
+use tokio::{sync::Notify, task::JoinHandle};
+use url::Url;
+
+const BOT_USER_ID: i64 = 1;
+const BOT_USERNAME: &str = "mest_net_test_bot";
+/// Upper bound of a `getUpdates` long poll, so that a test does not wait for
+/// the 10 seconds requested by the teloxide polling.
+const MAX_LONG_POLL_DURATION: Duration = Duration::from_millis(200);
+const REQUEST_WAIT_TIMEOUT: Duration = Duration::from_secs(10);
+const REQUEST_POLL_INTERVAL: Duration = Duration::from_millis(20);
+
+/// Bot API call made by the bot, with the result returned to it.
+#[derive(Clone, Debug)]
+pub(crate) struct BotApiRequest {
+    pub method: String,
+    pub params: Value,
+    pub result: Value,
+}
+
+impl BotApiRequest {
+    pub(crate) fn chat_id(&self) -> Option<i64> {
+        self.params["chat_id"].as_i64()
+    }
+
+    pub(crate) fn text(&self) -> &str {
+        self.params["text"].as_str().unwrap_or_default()
+    }
+
+    /// Texts of the buttons of a reply or an inline keyboard, row by row.
+    pub(crate) fn buttons(&self) -> Vec<String> {
+        let markup = &self.params["reply_markup"];
+        markup["keyboard"]
+            .as_array()
+            .or_else(|| markup["inline_keyboard"].as_array())
+            .into_iter()
+            .flatten()
+            .filter_map(Value::as_array)
+            .flatten()
+            .filter_map(|button| button["text"].as_str().map(str::to_owned))
+            .collect()
+    }
+
+    /// Callback data of the inline keyboard button with `text`.
+    pub(crate) fn callback_data(&self, text: &str) -> Option<String> {
+        self.params["reply_markup"]["inline_keyboard"]
+            .as_array()?
+            .iter()
+            .filter_map(Value::as_array)
+            .flatten()
+            .find(|button| button["text"] == text)
+            .and_then(|button| button["callback_data"].as_str().map(str::to_owned))
+    }
+}
+
+#[derive(Default)]
+struct FakeBotApiState {
+    requests: Mutex<Vec<BotApiRequest>>,
+    updates: Mutex<Vec<Value>>,
+    new_updates: Notify,
+    last_message_id: Mutex<i32>,
+}
+
+impl FakeBotApiState {
+    fn next_message_id(&self) -> i32 {
+        let mut last_message_id = self.last_message_id.lock().unwrap();
+        *last_message_id += 1;
+        *last_message_id
+    }
+}
+
+/// Local stand-in for the subset of the Telegram Bot API used by the bot. It
+/// serves queued updates through `getUpdates` and records every other call,
+/// answering it with a plausible result.
+pub(crate) struct FakeBotApi {
+    address: SocketAddr,
+    state: Arc<FakeBotApiState>,
+    server: JoinHandle<()>,
+}
+
+impl FakeBotApi {
+    pub(crate) fn start() -> Self {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap();
+        let state = Arc::new(FakeBotApiState::default());
+        let app = Router::new()
+            .route("/:bot_token/:method", post(handle_request))
+            .with_state(state.clone());
+        let server = tokio::spawn(async move {
+            if let Err(err) = axum::Server::from_tcp(listener)
+                .unwrap()
+                .serve(app.into_make_service())
+                .await
+            {
+                log::error!("Fake Bot API server failed: {err}");
+            }
+        });
+        Self {
+            address,
+            state,
+            server,
+        }
+    }
+
+    /// Bot sending its requests to this server.
+    pub(crate) fn bot(&self) -> Bot {
+        let url = Url::parse(&format!("http://{}/", self.address)).unwrap();
+        Bot::new("test-token").set_api_url(url)
+    }
+
+    pub(crate) fn next_message_id(&self) -> i32 {
+        self.state.next_message_id()
+    }
+
+    /// Queues an update for the next `getUpdates` call, `update_id` is set
+    /// here.
+    pub(crate) fn push_update(&self, mut update: Value) {
+        let mut updates = self.state.updates.lock().unwrap();
+        update["update_id"] = json!(updates.len() + 1);
+        updates.push(update);
+        self.state.new_updates.notify_waiters();
+    }
+
+    /// Waits for the first call of `method` matching `predicate`, which was
+    /// not taken before, and takes it.
+    pub(crate) async fn take_request(
+        &self,
+        method: &str,
+        predicate: impl Fn(&BotApiRequest) -> bool,
+    ) -> BotApiRequest {
+        let wait = async {
+            loop {
+                {
+                    let mut requests = self.state.requests.lock().unwrap();
+                    if let Some(index) = requests
+                        .iter()
+                        .position(|request| request.method == method && predicate(request))
+                    {
+                        return requests.remove(index);
+                    }
+                }
+                task::sleep(REQUEST_POLL_INTERVAL).await;
+            }
+        };
+        match timeout(REQUEST_WAIT_TIMEOUT, wait).await {
+            Ok(request) => request,
+            Err(_) => panic!(
+                "no {method} call was made, the bot called {:#?}",
+                self.state.requests.lock().unwrap()
+            ),
+        }
+    }
+}
+
+impl Drop for FakeBotApi {
+    fn drop(&mut self) {
+        self.server.abort();
+    }
+}
+
+async fn handle_request(
+    State(state): State<Arc<FakeBotApiState>>,
+    Path((_bot_token, method)): Path<(String, String)>,
+    body: Bytes,
+) -> Json<Value> {
+    // Method names are case insensitive, teloxide capitalizes them
+    let method: String = {
+        let mut chars = method.chars();
+        chars
+            .next()
+            .map(|first| first.to_lowercase().chain(chars).collect())
+            .unwrap_or_default()
+    };
+    let params: Value = serde_json::from_slice(&body).unwrap_or_else(|_| json!({}));
+    if method == "getUpdates" {
+        return Json(json!({ "ok": true, "result": get_updates(&state, &params).await }));
+    }
+    let result = match method.as_str() {
+        "getMe" => {
+            let mut me = bot_user();
+            me["can_join_groups"] = json!(false);
+            me["can_read_all_group_messages"] = json!(false);
+            me["supports_inline_queries"] = json!(false);
+            me
+        }
+        "getWebhookInfo" => json!({
+            "url": "",
+            "has_custom_certificate": false,
+            "pending_update_count": 0,
+        }),
+        "sendMessage" => json!({
+            "message_id": state.next_message_id(),
+            "date": 0,
+            "chat": private_chat(&params["chat_id"]),
+            "from": bot_user(),
+            "text": params["text"],
+        }),
+        "editMessageText" => json!({
+            "message_id": params["message_id"],
+            "date": 0,
+            "edit_date": 0,
+            "chat": private_chat(&params["chat_id"]),
+            "from": bot_user(),
+            "text": params["text"],
+        }),
+        "editMessageReplyMarkup" => json!({
+            "message_id": params["message_id"],
+            "date": 0,
+            "edit_date": 0,
+            "chat": private_chat(&params["chat_id"]),
+            "from": bot_user(),
+            "text": "",
+        }),
+        _ => json!(true),
+    };
+    state.requests.lock().unwrap().push(BotApiRequest {
+        method,
+        params,
+        result: result.clone(),
+    });
+    Json(json!({ "ok": true, "result": result }))
+}
+
+async fn get_updates(state: &FakeBotApiState, params: &Value) -> Value {
+    let offset = params["offset"].as_u64().unwrap_or_default() as usize;
+    let pending_updates = || -> Vec<Value> {
+        state
+            .updates
+            .lock()
+            .unwrap()
+            .iter()
+            .skip(offset.saturating_sub(1))
+            .cloned()
+            .collect()
+    };
+    let new_updates = state.new_updates.notified();
+    let mut updates = pending_updates();
+    if updates.is_empty() {
+        let _ = timeout(MAX_LONG_POLL_DURATION, new_updates).await;
+        updates = pending_updates();
+    }
+    Value::Array(updates)
+}
+
+fn bot_user() -> Value {
+    json!({
+        "id": BOT_USER_ID,
+        "is_bot": true,
+        "first_name": "Mest Net",
+        "username": BOT_USERNAME,
+    })
+}
+
+fn private_chat(chat_id: &Value) -> Value {
+    json!({ "id": chat_id, "type": "private", "first_name": "Test" })
+}
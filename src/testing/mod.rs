@@ -0,0 +1,4 @@
+//! End-to-end test harness: the bot talking to a fake Telegram Bot API.
+
+pub(crate) mod fake_bot_api;
+pub(crate) mod test_bot;
@@ -0,0 +1,327 @@
+use crate::{
+    background_processing::{
+        restaurant_registry::sync_restaurants, shutdown::Shutdown,
+        tasks::send_mest_check_notification,
+    },
+    config::AppConfig,
+    db::DatabaseHandler,
+    entity::restaurant::{Schedule, WorkingTime},
+    manager_tokens::{self, ManagerTokenAction, TokenActionOutcome},
+    model::{
+        booking_event::BookingEvent,
+        booking_info::BookingInfo,
+        mest_check_command::MestCheckCommand,
+        restaurant_record::RestaurantRecord,
+        state::State,
+        types::{Db, RestaurantsNumber, SharedFilterOptions},
+    },
+    monitoring::audit::TokenActor,
+    schema::schema,
+    testing::fake_bot_api::{BotApiRequest, FakeBotApi},
+};
+use chrono::NaiveTime;
+use rand::Rng;
+use sea_orm::{ConnectionTrait, DbBackend, Statement};
+use serde_json::{json, Value};
+use std::{
+    env,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+use teloxide::{
+    dispatching::dialogue::{InMemStorage, Storage},
+    prelude::*,
+};
+use tokio::{
+    sync::{broadcast, mpsc},
+    task::JoinHandle,
+};
+use toml::Table;
+
+const TEST_CONFIG: &str = r#"
+    [database]
+    url = "postgres://localhost/mest_net"
+
+    [telegram]
+    token = "test-token"
+
+    [dialogue_storage]
+    backend = "memory"
+
+    [admin]
+    super_admin_ids = [1000]
+"#;
+
+/// Telegram id of the super admin, out of the range of
+/// [`TestBot::new_user_id`].
+pub(crate) const SUPER_ADMIN_ID: i64 = 1000;
+
+/// Restaurant around a random location, so that searches of concurrently
+/// running tests do not reach each other restaurants.
+pub(crate) struct TestRestaurant {
+    pub id: i32,
+    pub external_id: String,
+    pub name: String,
+    pub manager_token: String,
+    pub longitude: f64,
+    pub latitude: f64,
+}
+
+/// The bot wired like in `main`, but talking to a [`FakeBotApi`]. It works
+/// with the database from `TEST_DATABASE_URL`, which needs PostGIS and the
+/// migrations applied, and adds one restaurant to it.
+pub(crate) struct TestBot {
+    pub api: FakeBotApi,
+    pub db_handler: DatabaseHandler,
+    pub restaurants_booking_info: Db<i32, BookingInfo>,
+    pub restaurant: TestRestaurant,
+    shutdown: Shutdown,
+    dispatcher: JoinHandle<()>,
+    /// Users given out by [`Self::new_user_id`], whose booking requests are
+    /// removed on [`Self::stop`].
+    user_ids: Mutex<Vec<i64>>,
+}
+
+impl TestBot {
+    /// Panics if `TEST_DATABASE_URL` is not set.
+    pub(crate) async fn start() -> Self {
+        let url = env::var("TEST_DATABASE_URL").expect("TEST_DATABASE_URL is not set");
+        let db_handler = DatabaseHandler::new(url).await.unwrap();
+        let restaurant = create_restaurant(&db_handler).await;
+        let config =
+            Arc::new(AppConfig::from_table(TEST_CONFIG.parse::<Table>().unwrap()).unwrap());
+        let api = FakeBotApi::start();
+        let bot = api.bot();
+
+        let (command_tx, command_rx) =
+            mpsc::channel::<MestCheckCommand>(config.channels.command_channel_size);
+        let (booking_event_tx, _) =
+            broadcast::channel::<BookingEvent>(config.channels.answer_channel_size);
+        let restaurants_booking_info: Db<i32, BookingInfo> = Arc::new(scc::HashMap::new());
+        let restaurants_number = RestaurantsNumber::default();
+        let filter_options = SharedFilterOptions::default();
+        sync_restaurants(
+            &db_handler,
+            &restaurants_booking_info,
+            &restaurants_number,
+            &filter_options,
+        )
+        .await
+        .unwrap();
+        let dialogue_storage = InMemStorage::<State>::new().erase();
+        let shutdown = Shutdown::default();
+
+        {
+            let bot = bot.clone();
+            let db_handler = db_handler.clone();
+            let restaurants_booking_info = restaurants_booking_info.clone();
+            let booking_event_tx = booking_event_tx.clone();
+            let config = config.clone();
+            let task_shutdown = shutdown.clone();
+            shutdown.spawn(async move {
+                send_mest_check_notification(
+                    bot,
+                    command_rx,
+                    booking_event_tx,
+                    db_handler,
+                    restaurants_booking_info,
+                    config,
+                    task_shutdown,
+                )
+                .await
+            });
+        }
+
+        let mut dispatcher = Dispatcher::builder(bot, schema())
+            .dependencies(dptree::deps![
+                db_handler.clone(),
+                dialogue_storage,
+                restaurants_booking_info.clone(),
+                command_tx,
+                booking_event_tx,
+                config,
+                shutdown.clone(),
+                restaurants_number,
+                filter_options
+            ])
+            .build();
+        let dispatcher = tokio::spawn(async move { dispatcher.dispatch().await });
+
+        Self {
+            api,
+            db_handler,
+            restaurants_booking_info,
+            restaurant,
+            shutdown,
+            dispatcher,
+            user_ids: Mutex::default(),
+        }
+    }
+
+    /// Stops the bot and removes the data of the test restaurant and the
+    /// booking requests of the test users.
+    pub(crate) async fn stop(self) {
+        self.shutdown.trigger();
+        self.shutdown.drain(Duration::ZERO).await;
+        self.dispatcher.abort();
+        for user_id in self.user_ids.into_inner().unwrap() {
+            self.db_handler
+                .db
+                .execute(Statement::from_sql_and_values(
+                    DbBackend::Postgres,
+                    "delete from booking_request where user_chat_id = $1",
+                    [user_id.into()],
+                ))
+                .await
+                .unwrap();
+        }
+        for sql in [
+            "delete from booking_request_answer where restaurant_id = $1",
+            "delete from booking_notification where restaurant_id = $1",
+            "delete from booking_hold where restaurant_id = $1",
+            "delete from manager where restaurant_id = $1",
+            "delete from restaurant where id = $1",
+        ] {
+            self.db_handler
+                .db
+                .execute(Statement::from_sql_and_values(
+                    DbBackend::Postgres,
+                    sql,
+                    [self.restaurant.id.into()],
+                ))
+                .await
+                .unwrap();
+        }
+    }
+
+    /// Random Telegram user id, which is also the id of the private chat.
+    pub(crate) fn new_user_id(&self) -> i64 {
+        let user_id = rand::thread_rng().gen_range(1_000_000..i64::MAX / 2);
+        self.user_ids.lock().unwrap().push(user_id);
+        user_id
+    }
+
+    pub(crate) fn send_text(&self, user_id: i64, text: &str) {
+        self.send_message(user_id, json!({ "text": text }));
+    }
+
+    pub(crate) fn send_location(&self, user_id: i64, longitude: f64, latitude: f64) {
+        self.send_message(
+            user_id,
+            json!({ "location": { "longitude": longitude, "latitude": latitude } }),
+        );
+    }
+
+    fn send_message(&self, user_id: i64, content: Value) {
+        let mut message = json!({
+            "message_id": self.api.next_message_id(),
+            "date": 0,
+            "chat": { "id": user_id, "type": "private", "first_name": "Test" },
+            "from": user(user_id),
+        });
+        message
+            .as_object_mut()
+            .unwrap()
+            .extend(content.as_object().unwrap().clone());
+        self.api.push_update(json!({ "message": message }));
+    }
+
+    /// Presses the inline keyboard button with `text` under `message`.
+    pub(crate) fn press_button(&self, user_id: i64, message: &BotApiRequest, text: &str) {
+        let data = message
+            .callback_data(text)
+            .unwrap_or_else(|| panic!("no {text} button in {message:#?}"));
+        self.send_callback_data(user_id, message, &data);
+    }
+
+    /// Sends a callback query with arbitrary `data` under `message`, as a
+    /// stale or crafted button would.
+    pub(crate) fn send_callback_data(&self, user_id: i64, message: &BotApiRequest, data: &str) {
+        self.api.push_update(json!({
+            "callback_query": {
+                "id": self.api.next_message_id().to_string(),
+                "from": user(user_id),
+                "chat_instance": user_id.to_string(),
+                "message": message.result,
+                "data": data,
+            }
+        }));
+    }
+
+    /// Waits for the next message sent to the chat with `chat_id`.
+    pub(crate) async fn next_message(&self, chat_id: i64) -> BotApiRequest {
+        self.api
+            .take_request("sendMessage", |request| request.chat_id() == Some(chat_id))
+            .await
+    }
+
+    /// Waits for the next edit of a message in the chat with `chat_id`.
+    pub(crate) async fn next_edit(&self, chat_id: i64) -> BotApiRequest {
+        self.api
+            .take_request("editMessageText", |request| {
+                request.chat_id() == Some(chat_id)
+            })
+            .await
+    }
+
+    /// Waits for the answer to the last pressed button.
+    pub(crate) async fn next_callback_answer(&self) -> BotApiRequest {
+        self.api.take_request("answerCallbackQuery", |_| true).await
+    }
+}
+
+async fn create_restaurant(db_handler: &DatabaseHandler) -> TestRestaurant {
+    let (longitude, latitude, suffix) = {
+        let mut rng = rand::thread_rng();
+        (
+            rng.gen_range(-170.0..170.0),
+            rng.gen_range(-60.0..60.0),
+            rng.gen::<u32>(),
+        )
+    };
+    let name = format!("Test restaurant {suffix}");
+    let external_id = format!("test-restaurant-{suffix}");
+    let record = RestaurantRecord {
+        external_id: external_id.clone(),
+        name: name.clone(),
+        maps_url: "https://maps.example".to_owned(),
+        average_price: "1000 ₽".to_owned(),
+        segment: "₽₽".to_owned(),
+        kitchen: "Европейская".to_owned(),
+        phone_number: "+70000000000".to_owned(),
+        longitude,
+        latitude,
+        time_zone: "Europe/Moscow".to_owned(),
+        schedule: Schedule::Regular {
+            working_time: WorkingTime {
+                start_time: NaiveTime::MIN,
+                end_time: NaiveTime::from_hms_nano_opt(23, 59, 59, 999_999_999).unwrap(),
+            },
+        },
+    };
+    let id = db_handler.upsert_restaurants(&[record]).await.unwrap()[0];
+    let TokenActionOutcome::Issued(issued) = manager_tokens::apply(
+        db_handler,
+        id,
+        ManagerTokenAction::Issue,
+        1,
+        TokenActor::Cli,
+    )
+    .await
+    .unwrap() else {
+        panic!("no token issued for a new restaurant");
+    };
+    let manager_token = issued.token;
+    TestRestaurant {
+        id,
+        external_id,
+        name,
+        manager_token,
+        longitude,
+        latitude,
+    }
+}
+
+fn user(user_id: i64) -> Value {
+    json!({ "id": user_id, "is_bot": false, "first_name": "Test" })
+}
@@ -0,0 +1,100 @@
+use crate::config::SearchConfig;
+
+/// Formats a distance in meters as "500 м", "1 км" or "1.5 км".
+pub(crate) fn format_distance(meters: u32) -> String {
+    if meters < 1000 {
+        format!("{} м", meters)
+    } else if meters % 1000 == 0 {
+        format!("{} км", meters / 1000)
+    } else {
+        format!("{:.1} км", meters as f64 / 1000.0)
+    }
+}
+
+/// Formats a rough walking time for a distance in meters.
+pub(crate) fn format_walking_time(meters: f64, search_config: &SearchConfig) -> String {
+    let minutes = (meters / search_config.walking_speed_in_meters_per_minute)
+        .ceil()
+        .max(1.0);
+    format!("~{} мин пешком", minutes as u32)
+}
+
+/// Resolves a search radius keyboard variant back to meters.
+pub(crate) fn parse_search_radius(text: &str, search_config: &SearchConfig) -> Option<u16> {
+    search_config
+        .radius_variants_in_meters
+        .iter()
+        .copied()
+        .find(|search_radius| format_distance(*search_radius as u32) == text)
+}
+
+/// Returns the radius of the automatic second search round, if the first
+/// round radius can still be widened.
+pub(crate) fn expand_search_radius(
+    search_radius: u16,
+    search_config: &SearchConfig,
+) -> Option<u16> {
+    let expanded_search_radius = search_radius
+        .saturating_mul(search_config.radius_expansion_factor)
+        .min(search_config.max_radius_in_meters);
+    (expanded_search_radius > search_radius).then_some(expanded_search_radius)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        config::SearchConfig,
+        utils::distance::{
+            expand_search_radius, format_distance, format_walking_time, parse_search_radius,
+        },
+    };
+
+    #[test]
+    fn distance_formatting() {
+        assert_eq!(format_distance(500), "500 м");
+        assert_eq!(format_distance(1000), "1 км");
+        assert_eq!(format_distance(1500), "1.5 км");
+    }
+
+    #[test]
+    fn walking_time_formatting() {
+        assert_eq!(
+            format_walking_time(10.0, &SearchConfig::default()),
+            "~1 мин пешком"
+        );
+        assert_eq!(
+            format_walking_time(800.0, &SearchConfig::default()),
+            "~10 мин пешком"
+        );
+        assert_eq!(
+            format_walking_time(810.0, &SearchConfig::default()),
+            "~11 мин пешком"
+        );
+    }
+
+    #[test]
+    fn search_radius_variants_parsing() {
+        assert_eq!(
+            parse_search_radius("500 м", &SearchConfig::default()),
+            Some(500)
+        );
+        assert_eq!(
+            parse_search_radius("3 км", &SearchConfig::default()),
+            Some(3000)
+        );
+        assert_eq!(parse_search_radius("2 км", &SearchConfig::default()), None);
+    }
+
+    #[test]
+    fn search_radius_expansion() {
+        assert_eq!(
+            expand_search_radius(500, &SearchConfig::default()),
+            Some(1500)
+        );
+        assert_eq!(
+            expand_search_radius(3000, &SearchConfig::default()),
+            Some(5000)
+        );
+        assert_eq!(expand_search_radius(5000, &SearchConfig::default()), None);
+    }
+}
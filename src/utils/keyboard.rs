@@ -1,9 +1,20 @@
-use crate::utils::constants::SEARCH_REQUEST_MESSAGE;
+use crate::{
+    config::{BookingConfig, SearchConfig},
+    entity::booking_request::PriceBand,
+    model::callback_data::CallbackData,
+    utils::constants::{
+        ANY_FILTER_VALUE_MESSAGE, LARGE_GROUP_REQUEST_MESSAGE, NO_FILTERS_REQUEST_MESSAGE,
+        SEARCH_REQUEST_MESSAGE,
+    },
+};
 
 use lazy_static::lazy_static;
-use teloxide::types::{ButtonRequest, KeyboardButton, KeyboardMarkup};
+use sea_orm::Iterable;
+use teloxide::types::{
+    ButtonRequest, InlineKeyboardButton, InlineKeyboardMarkup, KeyboardButton, KeyboardMarkup,
+};
 
-use super::constants::{MAX_SUPPORTED_PERSONS, MIN_SUPPORTED_PERSONS};
+use super::distance::format_distance;
 
 pub fn make_location_keyboard() -> KeyboardMarkup {
     let mut keyboard: Vec<Vec<KeyboardButton>> = vec![];
@@ -13,15 +24,11 @@ pub fn make_location_keyboard() -> KeyboardMarkup {
     keyboard.push(row);
     let mut markup = KeyboardMarkup::new(keyboard);
     markup.resize_keyboard = Option::from(true);
+    markup.one_time_keyboard = Option::from(true);
     markup
 }
 
 lazy_static! {
-    static ref SUPPORTED_PERSONS_VARIANTS: Vec<String> = {
-        (MIN_SUPPORTED_PERSONS..=MAX_SUPPORTED_PERSONS)
-            .map(|i| i.to_string())
-            .collect()
-    };
     static ref SEARCH_VARIANTS: Vec<String> = vec![SEARCH_REQUEST_MESSAGE.to_owned()];
     static ref ROLE_VARIANTS: Vec<String> = vec![
         "Обычный пользователь".to_owned(),
@@ -30,8 +37,50 @@ lazy_static! {
     static ref ANSWER_VARIANTS: Vec<String> = vec!["Да".to_owned(), "Нет".to_owned()];
 }
 
-pub fn make_number_keyboard() -> KeyboardMarkup {
-    make_keyborad_from_string(&SUPPORTED_PERSONS_VARIANTS)
+pub fn make_number_keyboard(booking_config: &BookingConfig) -> KeyboardMarkup {
+    let variants = (booking_config.min_supported_persons..booking_config.large_group_min_persons)
+        .map(|i| i.to_string())
+        .chain([LARGE_GROUP_REQUEST_MESSAGE.to_owned()])
+        .collect::<Vec<String>>();
+    make_keyborad_from_string(&variants)
+}
+
+pub fn make_search_radius_keyboard(search_config: &SearchConfig) -> KeyboardMarkup {
+    let variants = search_config
+        .radius_variants_in_meters
+        .iter()
+        .map(|search_radius| format_distance(*search_radius as u32))
+        .collect::<Vec<String>>();
+    make_keyborad_from_string(&variants)
+}
+
+pub fn make_kitchen_filter_keyboard(kitchens: &[String]) -> KeyboardMarkup {
+    let variants = kitchens
+        .iter()
+        .cloned()
+        .chain([
+            ANY_FILTER_VALUE_MESSAGE.to_owned(),
+            NO_FILTERS_REQUEST_MESSAGE.to_owned(),
+        ])
+        .collect::<Vec<String>>();
+    make_keyborad_from_string(&variants)
+}
+
+pub fn make_segment_filter_keyboard(segments: &[String]) -> KeyboardMarkup {
+    let variants = segments
+        .iter()
+        .cloned()
+        .chain([ANY_FILTER_VALUE_MESSAGE.to_owned()])
+        .collect::<Vec<String>>();
+    make_keyborad_from_string(&variants)
+}
+
+pub fn make_price_band_filter_keyboard(search_config: &SearchConfig) -> KeyboardMarkup {
+    let variants = PriceBand::iter()
+        .map(|price_band| price_band.label(search_config))
+        .chain([ANY_FILTER_VALUE_MESSAGE.to_owned()])
+        .collect::<Vec<String>>();
+    make_keyborad_from_string(&variants)
 }
 
 pub fn make_search_keyboard() -> KeyboardMarkup {
@@ -46,6 +95,50 @@ pub fn make_answer_keyboard() -> KeyboardMarkup {
     make_keyborad_from_string(&ANSWER_VARIANTS)
 }
 
+pub fn make_booking_request_answer_keyboard(booking_request_id: i32) -> InlineKeyboardMarkup {
+    let row = [("Да", true), ("Нет", false)]
+        .into_iter()
+        .map(|(text, approved)| {
+            InlineKeyboardButton::callback(
+                text,
+                CallbackData::BookingAnswer {
+                    booking_request_id,
+                    approved,
+                }
+                .to_string(),
+            )
+        })
+        .collect::<Vec<InlineKeyboardButton>>();
+    InlineKeyboardMarkup::new(vec![row])
+}
+
+pub fn make_restaurant_selection_keyboard(
+    booking_request_id: i32,
+    restaurants: &[(i32, String)],
+) -> InlineKeyboardMarkup {
+    let keyboard = restaurants
+        .iter()
+        .map(|(restaurant_id, name)| {
+            vec![InlineKeyboardButton::callback(
+                format!("Пойдем в {}", name),
+                CallbackData::RestaurantSelection {
+                    booking_request_id,
+                    restaurant_id: *restaurant_id,
+                }
+                .to_string(),
+            )]
+        })
+        .collect::<Vec<Vec<InlineKeyboardButton>>>();
+    InlineKeyboardMarkup::new(keyboard)
+}
+
+pub fn make_cancel_search_keyboard(booking_request_id: i32) -> InlineKeyboardMarkup {
+    InlineKeyboardMarkup::new(vec![vec![InlineKeyboardButton::callback(
+        "Отменить поиск",
+        CallbackData::CancelSearch { booking_request_id }.to_string(),
+    )]])
+}
+
 fn make_keyborad_from_string(variants: &[String]) -> KeyboardMarkup {
     let mut keyboard: Vec<Vec<KeyboardButton>> = vec![];
 
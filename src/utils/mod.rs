@@ -1,2 +1,3 @@
 pub(crate) mod constants;
+pub(crate) mod distance;
 pub(crate) mod keyboard;